@@ -0,0 +1,120 @@
+use crate::compressor::{self, CompressOptions, CompressionResult, CompressionStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const CACHE_FILE: &str = "result-cache.json";
+
+/// Bump when an encoder change makes previously cached results stale.
+const CACHE_VERSION: &[u8] = b"picslim-cache-v1";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub output_size: u64,
+    /// Compression didn't beat the original, so the original was kept.
+    pub already_optimal: bool,
+}
+
+/// Results of earlier runs keyed by input content hash + compression options,
+/// so unchanged inputs are skipped on incremental runs over the same folder.
+pub struct ResultCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl ResultCache {
+    pub fn open(data_dir: &Path) -> Self {
+        let path = data_dir.join(CACHE_FILE);
+        let entries = std::fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    pub fn key(input_path: &Path, options: &CompressOptions) -> Option<String> {
+        let data = std::fs::read(input_path).ok()?;
+        let options = serde_json::to_vec(options).ok()?;
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(CACHE_VERSION);
+        hasher.update(&options);
+        hasher.update(&data);
+        Some(hasher.finalize().to_hex().to_string())
+    }
+
+    /// Result for an input already processed with the same options, if its
+    /// output can be reused. Already-optimal inputs are re-copied when the
+    /// output is missing, since that costs no more than a lookup.
+    pub fn reuse(
+        &self,
+        key: &str,
+        input_path: &Path,
+        output_path: &Path,
+        options: &CompressOptions,
+    ) -> Option<CompressionResult> {
+        let entry = *self.entries.lock().ok()?.get(key)?;
+        let original_size = std::fs::metadata(input_path).ok()?.len();
+        let file_size = |path: &Path| std::fs::metadata(path).map(|m| m.len()).ok();
+
+        let written = if entry.already_optimal && options.skip_unoptimized {
+            input_path.to_path_buf()
+        } else if entry.already_optimal {
+            let kept_path = compressor::kept_original_path(input_path, output_path);
+            if file_size(&kept_path) != Some(original_size) {
+                compressor::copy_into_place(input_path, &kept_path).ok()?;
+            }
+            kept_path
+        } else if file_size(output_path) == Some(entry.output_size) {
+            output_path.to_path_buf()
+        } else {
+            return None;
+        };
+
+        Some(CompressionResult {
+            status: CompressionStatus::Skipped,
+            success: true,
+            original_size,
+            compressed_size: entry.output_size,
+            output_path: written.to_string_lossy().to_string(),
+            error: None,
+        })
+    }
+
+    pub fn insert(&self, key: &str, result: &CompressionResult) {
+        let already_optimal = match result.status {
+            CompressionStatus::Compressed => false,
+            CompressionStatus::KeptOriginal => true,
+            CompressionStatus::Skipped | CompressionStatus::Failed => return,
+        };
+        let entry = CacheEntry {
+            output_size: result.compressed_size,
+            already_optimal,
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(key.to_string(), entry);
+        }
+    }
+
+    pub fn save(&self) {
+        let Ok(entries) = self.entries.lock() else {
+            return;
+        };
+        let Some(dir) = self.path.parent() else {
+            return;
+        };
+        let _ = std::fs::create_dir_all(dir);
+
+        // Write to a temp file and rename, so a crash never leaves the cache half-written
+        let _ = serde_json::to_vec(&*entries).ok().and_then(|data| {
+            let mut tmp = tempfile::NamedTempFile::new_in(dir).ok()?;
+            tmp.write_all(&data).ok()?;
+            tmp.persist(&self.path).ok()
+        });
+    }
+}
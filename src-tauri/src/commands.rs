@@ -1,10 +1,16 @@
-use crate::compressor::{self, CompressionResult};
+use crate::cache::ResultCache;
+use crate::compressor::{self, CompressOptions, CompressionResult, Stage};
+use crate::job::{JobControl, JobStatus};
+use crate::journal::{self, ItemStatus, Journal};
 use crate::thumbnail;
 use crate::utils::{self, ImageFormat};
-use rayon::prelude::*;
+use crate::worker;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageFileInfo {
@@ -15,57 +21,110 @@ pub struct ImageFileInfo {
     pub size_display: String,
     pub format: ImageFormat,
     pub thumbnail: String,
+    /// Quality a JPEG was saved at, estimated from its quantization tables.
+    #[serde(default)]
+    pub estimated_quality: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressionSettings {
-    pub quality: u32,
+    #[serde(flatten)]
+    pub options: CompressOptions,
     pub output_dir: String,
+    /// Maximum number of worker threads (0 = one per CPU core).
+    #[serde(default)]
+    pub max_workers: usize,
+    /// Run workers at lowered OS scheduling priority.
+    #[serde(default)]
+    pub background: bool,
+    /// Recompress everything, even inputs the result cache has already seen.
+    #[serde(default)]
+    pub ignore_cache: bool,
 }
 
+/// Events streamed to the frontend while a batch runs, tagged by `event_type`.
 #[derive(Debug, Clone, Serialize)]
-pub struct CompressionEvent {
-    pub event_type: String, // "start", "complete", "error"
-    pub image_id: String,
-    pub result: Option<CompressionResult>,
+#[serde(tag = "event_type", rename_all = "snake_case")]
+pub enum CompressionEvent {
+    Start {
+        image_id: String,
+    },
+    /// Progress within one image; `progress` is the 0.0–1.0 fraction of `stage`.
+    Stage {
+        image_id: String,
+        stage: Stage,
+        progress: f32,
+    },
+    Complete {
+        image_id: String,
+        result: CompressionResult,
+    },
+    Error {
+        image_id: String,
+        result: CompressionResult,
+    },
+    /// Batch-level progress, sent after every finished image.
+    Batch {
+        done: usize,
+        total: usize,
+        elapsed_ms: u64,
+        /// Estimated remaining time, weighted by input bytes. `None` until one image finishes.
+        eta_ms: Option<u64>,
+    },
+}
+
+/// Unfinished part of a batch interrupted by an app exit or crash.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumableJob {
+    pub settings: CompressionSettings,
+    /// Images still to compress, ready to pass back to `compress_images`.
+    pub images: Vec<ImageFileInfo>,
+    /// Images that finished before the interruption, or whose output was found intact.
+    pub finished: usize,
 }
 
 #[tauri::command]
 pub fn load_images(paths: Vec<String>) -> Vec<ImageFileInfo> {
-    paths
-        .into_iter()
-        .filter_map(|p| {
-            let path = Path::new(&p);
-            if !path.exists() || !path.is_file() {
-                return None;
-            }
+    paths.into_iter().filter_map(load_image_info).collect()
+}
 
-            let format = utils::detect_format(path)?;
-            let metadata = std::fs::metadata(path).ok()?;
-            let size = metadata.len();
-            let name = path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-
-            let thumbnail = thumbnail::generate_thumbnail(path).unwrap_or_default();
-
-            Some(ImageFileInfo {
-                id: uuid::Uuid::new_v4().to_string(),
-                path: p,
-                name,
-                size,
-                size_display: utils::format_file_size(size),
-                format,
-                thumbnail,
-            })
-        })
-        .collect()
+fn load_image_info(p: String) -> Option<ImageFileInfo> {
+    let path = Path::new(&p);
+    if !path.exists() || !path.is_file() {
+        return None;
+    }
+
+    let format = utils::detect_format(path)?;
+    let metadata = std::fs::metadata(path).ok()?;
+    let size = metadata.len();
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let thumbnail = thumbnail::generate_thumbnail(path).unwrap_or_default();
+    let estimated_quality = match format {
+        ImageFormat::Jpeg => compressor::jpeg::estimate_quality_file(path),
+        _ => None,
+    };
+
+    Some(ImageFileInfo {
+        id: uuid::Uuid::new_v4().to_string(),
+        path: p,
+        name,
+        size,
+        size_display: utils::format_file_size(size),
+        format,
+        thumbnail,
+        estimated_quality,
+    })
 }
 
-#[tauri::command]
+// Runs off the IPC thread so pause/resume/status commands stay responsive mid-batch
+#[tauri::command(async)]
 pub fn compress_images(
+    app: AppHandle,
     images: Vec<ImageFileInfo>,
     settings: CompressionSettings,
     on_event: Channel<CompressionEvent>,
@@ -73,41 +132,205 @@ pub fn compress_images(
     let output_dir = PathBuf::from(&settings.output_dir);
     let _ = utils::ensure_output_dir(&output_dir);
 
-    let results: Vec<CompressionResult> = images
-        .par_iter()
-        .map(|img| {
-            // Emit start event
-            let _ = on_event.send(CompressionEvent {
-                event_type: "start".to_string(),
-                image_id: img.id.clone(),
-                result: None,
-            });
+    // Journal the batch so it can be resumed if the app exits mid-way
+    let journal = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .and_then(|dir| Journal::start(&dir, &settings, &images, &output_dir).ok());
 
-            let input_path = Path::new(&img.path);
-            let output_path = output_dir.join(&img.name);
+    let cache = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .filter(|_| !settings.ignore_cache)
+        .map(|dir| ResultCache::open(&dir));
 
-            let result =
-                compressor::compress(input_path, &output_path, img.format, settings.quality);
+    let job = app.state::<JobControl>();
+    let total = images.len();
+    let total_bytes: u64 = images.iter().map(|img| img.size).sum();
+    let done_bytes = AtomicU64::new(0);
+    job.begin(total);
 
-            // Emit complete or error event
-            let event_type = if result.success {
-                "complete"
-            } else {
-                "error"
-            };
-            let _ = on_event.send(CompressionEvent {
-                event_type: event_type.to_string(),
+    let compress_one = |img: &ImageFileInfo| {
+        let _ = on_event.send(CompressionEvent::Start {
+            image_id: img.id.clone(),
+        });
+
+        let input_path = Path::new(&img.path);
+        let output_path = output_dir.join(compressor::output_name(
+            &img.name,
+            img.format,
+            &settings.options,
+        ));
+
+        let report = |stage: Stage, progress: f32| {
+            let _ = on_event.send(CompressionEvent::Stage {
                 image_id: img.id.clone(),
-                result: Some(result.clone()),
+                stage,
+                progress,
             });
+        };
+        let cache_key = cache
+            .as_ref()
+            .and_then(|_| ResultCache::key(input_path, &settings.options));
+        let cached = cache
+            .as_ref()
+            .zip(cache_key.as_deref())
+            .and_then(|(cache, key)| cache.reuse(key, input_path, &output_path, &settings.options));
 
+        let result = cached.unwrap_or_else(|| {
+            let result = compressor::compress(
+                input_path,
+                &output_path,
+                img.format,
+                &settings.options,
+                &report,
+            );
+            if let (Some(cache), Some(key)) = (&cache, &cache_key) {
+                cache.insert(key, &result);
+            }
             result
-        })
+        });
+
+        let event = if result.success {
+            CompressionEvent::Complete {
+                image_id: img.id.clone(),
+                result: result.clone(),
+            }
+        } else {
+            CompressionEvent::Error {
+                image_id: img.id.clone(),
+                result: result.clone(),
+            }
+        };
+        let _ = on_event.send(event);
+        if let Some(journal) = &journal {
+            journal.record(&img.id, &result);
+        }
+
+        let done = job.release();
+        let done_bytes = done_bytes.fetch_add(img.size, Ordering::Relaxed) + img.size;
+        let elapsed = job.active_elapsed().as_millis() as u64;
+        let eta_ms = (done_bytes > 0).then(|| {
+            let remaining = total_bytes.saturating_sub(done_bytes);
+            (elapsed as f64 * remaining as f64 / done_bytes as f64) as u64
+        });
+        let _ = on_event.send(CompressionEvent::Batch {
+            done,
+            total,
+            elapsed_ms: elapsed,
+            eta_ms,
+        });
+
+        result
+    };
+
+    // Fall back to rayon's global pool if a dedicated one can't be built
+    let (max_workers, background) = app
+        .state::<worker::CliOverrides>()
+        .apply(settings.max_workers, settings.background);
+    let pool = worker::build_pool(max_workers, background).ok();
+    let workers = pool
+        .as_ref()
+        .map_or_else(rayon::current_num_threads, |pool| {
+            pool.current_num_threads()
+        });
+    let results = Mutex::new(vec![None; total]);
+
+    // Queue items are handed to the pool from this thread, one per free
+    // worker and only while the batch runs. Pausing holds them here, so
+    // pool threads never park and in-flight images keep every worker.
+    let dispatch = |scope: &_| {
+        for (i, img) in images.iter().enumerate() {
+            job.acquire(workers);
+            let (compress_one, results) = (&compress_one, &results);
+            rayon::Scope::spawn(scope, move |_| {
+                let result = compress_one(img);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    };
+    match &pool {
+        Some(pool) => pool.in_place_scope(dispatch),
+        None => rayon::in_place_scope(dispatch),
+    }
+    let results: Vec<CompressionResult> = results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .flatten()
         .collect();
 
+    job.finish();
+    if let Some(cache) = cache {
+        cache.save();
+    }
+    if let Some(journal) = journal {
+        journal.finish();
+    }
     results
 }
 
+/// Check for a batch left unfinished by the previous run. Pending images whose
+/// output already exists and decodes are counted as finished, not redone.
+#[tauri::command]
+pub fn get_resumable_job(app: AppHandle) -> Option<ResumableJob> {
+    let data_dir = app.path().app_data_dir().ok()?;
+    let recovered = journal::load(&data_dir)?;
+
+    let mut images = Vec::new();
+    let mut finished = 0;
+    for item in recovered.items {
+        if item.status != ItemStatus::Pending
+            || journal::output_is_valid(Path::new(&item.output_path))
+        {
+            finished += 1;
+            continue;
+        }
+        // Inputs that were moved or deleted since can't be resumed
+        if let Some(info) = load_image_info(item.path) {
+            images.push(ImageFileInfo {
+                id: item.id,
+                ..info
+            });
+        }
+    }
+
+    if images.is_empty() {
+        journal::discard(&data_dir);
+        return None;
+    }
+
+    Some(ResumableJob {
+        settings: recovered.settings,
+        images,
+        finished,
+    })
+}
+
+#[tauri::command]
+pub fn discard_resumable_job(app: AppHandle) {
+    if let Ok(data_dir) = app.path().app_data_dir() {
+        journal::discard(&data_dir);
+    }
+}
+
+#[tauri::command]
+pub fn pause_compression(app: AppHandle) -> bool {
+    app.state::<JobControl>().pause()
+}
+
+#[tauri::command]
+pub fn resume_compression(app: AppHandle) -> bool {
+    app.state::<JobControl>().resume()
+}
+
+#[tauri::command]
+pub fn get_job_status(app: AppHandle) -> JobStatus {
+    app.state::<JobControl>().status()
+}
+
 #[tauri::command]
 pub fn open_output_folder(path: String) -> Result<(), String> {
     #[cfg(target_os = "windows")]
@@ -1,8 +1,12 @@
+use super::{CompressOptions, MetadataPolicy, Progress, Stage};
+use libwebp_sys as webp;
 use rayon::prelude::*;
+use std::collections::HashMap;
+use std::io::Read;
 use std::path::Path;
 
-/// Memory threshold for parallel processing (512 MB).
-/// GIFs exceeding this estimated memory usage fall back to sequential processing.
+/// Memory budget for one batch of frames quantized in parallel (512 MB).
+/// Large canvases get smaller batches, down to one frame at a time.
 const PARALLEL_MEMORY_LIMIT: usize = 512 * 1024 * 1024;
 
 /// Quality-adaptive compression parameters derived from the user quality slider (60–95).
@@ -11,6 +15,9 @@ struct QualityParams {
     /// Lossy tolerance: pixels within this RGBA distance are merged for better LZW runs.
     /// 0 means lossless (no pixel alignment).
     lossy_tolerance: u8,
+    /// Max RGB distance the lossy LZW encoder may substitute a palette color
+    /// by to extend a dictionary match. 0 uses the exact encoder.
+    lzw_tolerance: u16,
 }
 
 fn quality_params(quality: u32) -> QualityParams {
@@ -19,23 +26,96 @@ fn quality_params(quality: u32) -> QualityParams {
         90..=u32::MAX => QualityParams {
             dithering: 0.65,
             lossy_tolerance: 0,
+            lzw_tolerance: 0,
         },
         75..=89 => QualityParams {
             dithering: 0.45,
             lossy_tolerance: 2,
+            lzw_tolerance: 12,
         },
         _ => QualityParams {
             dithering: 0.25,
             lossy_tolerance: 4,
+            lzw_tolerance: 24,
         },
     }
 }
 
+/// How many times the output animation plays.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopMode {
+    /// Keep the source's loop count.
+    #[default]
+    Source,
+    Infinite,
+    /// Play this many times in total, then stop on the last frame.
+    Plays(u32),
+}
+
+/// Format the animation is written as.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GifTarget {
+    #[default]
+    Gif,
+    /// Animated WebP, lossy; typically far smaller than the GIF.
+    Webp,
+    WebpLossless,
+    /// Animated PNG, with one palette shared by all frames.
+    Apng,
+}
+
+impl GifTarget {
+    /// File extension of the output, when it isn't a GIF.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            GifTarget::Gif => None,
+            GifTarget::Webp | GifTarget::WebpLossless => Some("webp"),
+            GifTarget::Apng => Some("png"),
+        }
+    }
+}
+
+/// GIF encoder settings.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct GifOptions {
+    /// Quantize all frames together into one global color table instead of a
+    /// local palette per frame. Saves up to 768 bytes per frame and stops
+    /// colors flickering; frames it can't represent well keep a local palette.
+    pub global_palette: bool,
+    /// Drop frames so the animation plays at no more than this many frames
+    /// per second, keeping its total duration. 0 = unlimited.
+    pub max_fps: u32,
+    pub loop_mode: LoopMode,
+    /// Playback speed multiplier: 2.0 plays twice as fast, 0.5 at half speed.
+    pub speed: f32,
+    /// Raise shorter frame delays to this many centiseconds. 0 = off.
+    pub min_delay: u16,
+    /// Convert the animation to another format; the output takes its extension.
+    pub target: GifTarget,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            global_palette: false,
+            max_fps: 0,
+            loop_mode: LoopMode::Source,
+            speed: 1.0,
+            min_delay: 0,
+            target: GifTarget::Gif,
+        }
+    }
+}
+
 /// Decoded frame with full-canvas RGBA pixels and original timing metadata.
 struct DecodedFrame {
     /// Full canvas RGBA (width * height * 4 bytes).
     canvas: Vec<u8>,
     delay: u16,
+    needs_user_input: bool,
 }
 
 /// Delta-optimized frame ready for quantization and encoding.
@@ -48,91 +128,340 @@ struct DeltaFrame {
     top: u16,
     delay: u16,
     dispose: gif::DisposalMethod,
+    needs_user_input: bool,
 }
 
-pub fn compress(input_path: &Path, output_path: &Path, quality: u32) -> Result<u64, String> {
-    let quality = quality.clamp(60, 95);
+pub fn compress(
+    input_path: &Path,
+    output_path: &Path,
+    options: &CompressOptions,
+    progress: Progress,
+) -> Result<u64, String> {
+    let quality = options.quality.clamp(60, 95);
     let params = quality_params(quality);
 
-    // --- Phase 1: Decode all frames to full-canvas RGBA ---
-    let (canvas_w, canvas_h, repeat, decoded_frames) = decode_all_frames(input_path)?;
+    // Frames are decoded as they are encoded; count them first for progress
+    progress(Stage::Decode, 0.0);
+    let source = scan_source(input_path, options.metadata)?;
+    progress(Stage::Decode, 1.0);
 
-    let is_single_frame = decoded_frames.len() <= 1;
-    let estimated_memory = canvas_w as usize * canvas_h as usize * 4 * decoded_frames.len();
-    let use_parallel = !is_single_frame && estimated_memory <= PARALLEL_MEMORY_LIMIT;
+    // Conversions reuse the same frames and deltas in another container
+    match options.gif.target {
+        GifTarget::Gif => encode_gif(
+            input_path,
+            output_path,
+            options,
+            quality,
+            &params,
+            &source,
+            progress,
+        )?,
+        GifTarget::Webp | GifTarget::WebpLossless => {
+            encode_webp(input_path, output_path, options, &params, &source, progress)?
+        }
+        GifTarget::Apng => encode_apng(
+            input_path,
+            output_path,
+            options,
+            quality,
+            &params,
+            &source,
+            progress,
+        )?,
+    }
 
-    // --- Phase 2: Compute delta frames (sequential — each frame depends on the previous) ---
-    let delta_frames = compute_delta_frames(&decoded_frames, canvas_w, canvas_h, is_single_frame);
+    let compressed_size = std::fs::metadata(output_path)
+        .map(|m| m.len())
+        .map_err(|e| format!("출력 크기 확인 실패: {}", e))?;
 
-    // --- Phase 3: Quantize + LZW pre-encode (parallel when possible) ---
-    let encoded_frames = if use_parallel {
-        encode_frames_parallel(&delta_frames, quality, &params)
+    Ok(compressed_size)
+}
+
+fn encode_gif(
+    input_path: &Path,
+    output_path: &Path,
+    options: &CompressOptions,
+    quality: u32,
+    params: &QualityParams,
+    source: &SourceInfo,
+    progress: Progress,
+) -> Result<(), String> {
+    // The global color table is written before the first frame, so it gets
+    // its own pass over the animation
+    let global = if options.gif.global_palette && source.frames > 1 {
+        build_global_palette(input_path, quality, params, &options.gif)?.0
     } else {
-        encode_frames_sequential(&delta_frames, quality, &params)
+        None
     };
+    let global_palette = global
+        .as_ref()
+        .map(|g| palette_bytes(&g.palette))
+        .unwrap_or_default();
 
-    // --- Phase 4: Write all frames sequentially ---
-    write_gif(output_path, canvas_w, canvas_h, repeat, &encoded_frames)?;
+    let mut stream = FrameStream::open(input_path, params, &options.gif)?;
+    let (canvas_w, canvas_h) = (stream.decoder.width, stream.decoder.height);
+    let mut encoder = create_encoder(
+        output_path,
+        canvas_w,
+        canvas_h,
+        output_repeat(&options.gif, stream.decoder.repeat),
+        &global_palette,
+        &source.extensions,
+    )?;
 
-    let compressed_size = std::fs::metadata(output_path)
-        .map(|m| m.len())
-        .map_err(|e| format!("GIF 크기 확인 실패: {}", e))?;
+    // Quantize up to one frame per thread at a time; only the current batch
+    // is held in memory
+    let canvas_bytes = canvas_w as usize * canvas_h as usize * 4;
+    let batch_size =
+        (PARALLEL_MEMORY_LIMIT / canvas_bytes.max(1)).clamp(1, rayon::current_num_threads());
+    let total = source.frames.max(1) as f32;
+    let mut batch: Vec<DeltaFrame> = Vec::with_capacity(batch_size);
 
-    Ok(compressed_size)
+    loop {
+        let next = stream.next_delta()?;
+        let finished = next.is_none();
+        batch.extend(next);
+
+        if batch.len() == batch_size || (finished && !batch.is_empty()) {
+            let done = stream.decoder.frames_read as f32 / total;
+            let frames = encode_batch(&batch, quality, params, global.as_ref());
+            progress(Stage::Quantize, done);
+            for frame in &frames {
+                encoder
+                    .write_lzw_pre_encoded_frame(frame)
+                    .map_err(|e| format!("GIF 프레임 쓰기 실패: {}", e))?;
+            }
+            progress(Stage::Encode, done);
+            batch.clear();
+        }
+        if finished {
+            break;
+        }
+    }
+    drop(encoder);
+    Ok(())
+}
+
+/// Repeat counts the loops after the first play.
+fn output_repeat(options: &GifOptions, source: gif::Repeat) -> gif::Repeat {
+    match options.loop_mode {
+        LoopMode::Source => source,
+        LoopMode::Infinite => gif::Repeat::Infinite,
+        LoopMode::Plays(plays) => {
+            gif::Repeat::Finite(plays.saturating_sub(1).min(u16::MAX as u32) as u16)
+        }
+    }
+}
+
+/// Total plays as WebP and APNG count them, 0 meaning forever.
+fn total_plays(repeat: gif::Repeat) -> u32 {
+    match repeat {
+        gif::Repeat::Infinite => 0,
+        gif::Repeat::Finite(loops) => loops as u32 + 1,
+    }
 }
 
 // ---------------------------------------------------------------------------
-// Phase 1: Full-canvas decoding
+// Phase 1: Streaming full-canvas decoding
 // ---------------------------------------------------------------------------
 
-fn decode_all_frames(input_path: &Path) -> Result<(u16, u16, gif::Repeat, Vec<DecodedFrame>), String> {
-    let file =
-        std::fs::File::open(input_path).map_err(|e| format!("GIF 열기 실패: {}", e))?;
-    let mut opts = gif::DecodeOptions::new();
-    opts.set_color_output(gif::ColorOutput::RGBA);
-    let mut reader = opts
-        .read_info(file)
-        .map_err(|e| format!("GIF 디코딩 실패: {}", e))?;
-
-    let w = reader.width();
-    let h = reader.height();
-    let repeat = reader.repeat();
-    let canvas_size = w as usize * h as usize * 4;
-
-    // Derive background fill color from global palette + bg_color index
-    let bg_fill = {
-        let bg_idx = reader.bg_color().unwrap_or(0);
-        match reader.global_palette() {
-            Some(pal) => {
-                let offset = bg_idx * 3;
-                if offset + 2 < pal.len() {
-                    [pal[offset], pal[offset + 1], pal[offset + 2], 0xFF]
-                } else {
-                    [0, 0, 0, 0]
+/// Loop-count application extensions, rewritten by `set_repeat`.
+const LOOP_EXTENSIONS: [&[u8]; 2] = [b"NETSCAPE2.0", b"ANIMEXTS1.0"];
+/// Application extension carrying an ICC color profile.
+const ICC_EXTENSION: &[u8] = b"ICCRGBG1012";
+/// Application extension carrying an XMP packet as raw bytes.
+const XMP_EXTENSION: &[u8] = b"XMP DataXMP";
+
+/// A comment or application extension copied verbatim from the source.
+struct RawExtension {
+    label: u8,
+    /// Data sub-blocks; for application extensions the first is the identifier.
+    blocks: Vec<Vec<u8>>,
+}
+
+/// What is read from the source's block structure without decompressing frames.
+struct SourceInfo {
+    frames: usize,
+    /// Extensions the metadata policy keeps, in file order.
+    extensions: Vec<RawExtension>,
+}
+
+impl SourceInfo {
+    fn application(&self, id: &[u8]) -> Option<&RawExtension> {
+        self.extensions
+            .iter()
+            .find(|ext| ext.label == 0xFF && ext.blocks.first().is_some_and(|b| b == id))
+    }
+
+    fn icc_profile(&self) -> Option<Vec<u8>> {
+        let ext = self.application(ICC_EXTENSION)?;
+        Some(ext.blocks[1..].concat())
+    }
+
+    /// The XMP packet. It is stored unblocked, so the sub-block length bytes
+    /// are part of it, followed by a 257-byte "magic trailer".
+    fn xmp_packet(&self) -> Option<Vec<u8>> {
+        let ext = self.application(XMP_EXTENSION)?;
+        let mut raw: Vec<u8> = Vec::new();
+        for block in &ext.blocks[1..] {
+            raw.push(block.len() as u8);
+            raw.extend_from_slice(block);
+        }
+        raw.truncate(raw.len().saturating_sub(257));
+        Some(raw)
+    }
+
+    fn comments(&self) -> impl Iterator<Item = String> + '_ {
+        self.extensions
+            .iter()
+            .filter(|ext| ext.label == 0xFE)
+            .map(|ext| String::from_utf8_lossy(&ext.blocks.concat()).into_owned())
+    }
+}
+
+fn scan_source(input_path: &Path, policy: MetadataPolicy) -> Result<SourceInfo, String> {
+    let file = std::fs::File::open(input_path).map_err(|e| format!("GIF 열기 실패: {}", e))?;
+    let mut reader = std::io::BufReader::new(file);
+    let mut info = SourceInfo {
+        frames: 0,
+        extensions: Vec::new(),
+    };
+    // A malformed file just ends the scan; the decoder reports the error
+    let _ = scan_blocks(&mut reader, policy, &mut info);
+    Ok(info)
+}
+
+fn scan_blocks(
+    reader: &mut impl Read,
+    policy: MetadataPolicy,
+    info: &mut SourceInfo,
+) -> std::io::Result<()> {
+    // Header and logical screen descriptor
+    let mut header = [0u8; 13];
+    reader.read_exact(&mut header)?;
+    skip_color_table(reader, header[10])?;
+
+    loop {
+        match read_byte(reader)? {
+            0x21 => {
+                let label = read_byte(reader)?;
+                let blocks = read_sub_blocks(reader)?;
+                if keeps_extension(label, &blocks, policy) {
+                    info.extensions.push(RawExtension { label, blocks });
+                }
+            }
+            0x2C => {
+                let mut descriptor = [0u8; 9];
+                reader.read_exact(&mut descriptor)?;
+                skip_color_table(reader, descriptor[8])?;
+                // LZW minimum code size, then the image data
+                read_byte(reader)?;
+                while let len @ 1.. = read_byte(reader)? {
+                    std::io::copy(&mut reader.take(len as u64), &mut std::io::sink())?;
                 }
+                info.frames += 1;
             }
-            None => [0, 0, 0, 0],
+            // Trailer
+            _ => return Ok(()),
         }
-    };
+    }
+}
 
-    let mut frames: Vec<DecodedFrame> = Vec::new();
-    // Canvas tracks the cumulative composited image (disposal-aware).
-    // Initialize with the GIF background color so uncovered areas are correct.
-    let mut canvas = vec![0u8; canvas_size];
-    for px in canvas.chunks_exact_mut(4) {
-        px.copy_from_slice(&bg_fill);
+fn keeps_extension(label: u8, blocks: &[Vec<u8>], policy: MetadataPolicy) -> bool {
+    let application = blocks.first().map(Vec::as_slice).unwrap_or_default();
+    match (policy, label) {
+        (MetadataPolicy::KeepAll, 0xFE) => true,
+        (MetadataPolicy::KeepAll, 0xFF) => !LOOP_EXTENSIONS.contains(&application),
+        (MetadataPolicy::KeepColorAndDpi, 0xFF) => application == ICC_EXTENSION,
+        _ => false,
     }
+}
+
+fn skip_color_table(reader: &mut impl Read, flags: u8) -> std::io::Result<()> {
+    if flags & 0x80 != 0 {
+        let len = 3u64 << ((flags & 0x07) + 1);
+        std::io::copy(&mut reader.take(len), &mut std::io::sink())?;
+    }
+    Ok(())
+}
+
+fn read_sub_blocks(reader: &mut impl Read) -> std::io::Result<Vec<Vec<u8>>> {
+    let mut blocks = Vec::new();
+    while let len @ 1.. = read_byte(reader)? {
+        let mut block = vec![0u8; len as usize];
+        reader.read_exact(&mut block)?;
+        blocks.push(block);
+    }
+    Ok(blocks)
+}
+
+fn read_byte(reader: &mut impl Read) -> std::io::Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+/// Reads frames one at a time, compositing each onto the full canvas.
+struct FrameDecoder {
+    reader: gif::Decoder<std::fs::File>,
+    width: u16,
+    height: u16,
+    repeat: gif::Repeat,
+    /// Cumulative composited image (disposal-aware).
+    canvas: Vec<u8>,
+    /// Source frames read so far.
+    frames_read: usize,
+}
+
+impl FrameDecoder {
+    fn open(input_path: &Path) -> Result<Self, String> {
+        let file = std::fs::File::open(input_path).map_err(|e| format!("GIF 열기 실패: {}", e))?;
+        let mut opts = gif::DecodeOptions::new();
+        opts.set_color_output(gif::ColorOutput::RGBA);
+        let reader = opts
+            .read_info(file)
+            .map_err(|e| format!("GIF 디코딩 실패: {}", e))?;
+
+        let w = reader.width();
+        let h = reader.height();
+        let repeat = reader.repeat();
+
+        // Browsers ignore the logical screen background color and start
+        // from a transparent canvas
+        let canvas = vec![0u8; w as usize * h as usize * 4];
+
+        Ok(Self {
+            reader,
+            width: w,
+            height: h,
+            repeat,
+            canvas,
+            frames_read: 0,
+        })
+    }
+
+    /// The next frame as a full canvas, or `None` after the last one.
+    fn next_frame(&mut self) -> Result<Option<DecodedFrame>, String> {
+        let w = self.width as usize;
+        let h = self.height as usize;
+        let canvas = &mut self.canvas;
+
+        let Some(frame) = self
+            .reader
+            .read_next_frame()
+            .map_err(|e| format!("GIF 프레임 읽기 실패: {}", e))?
+        else {
+            return Ok(None);
+        };
+        self.frames_read += 1;
 
-    while let Some(frame) = reader
-        .read_next_frame()
-        .map_err(|e| format!("GIF 프레임 읽기 실패: {}", e))?
-    {
         let fl = frame.left as usize;
         let ft = frame.top as usize;
         let fw = frame.width as usize;
         let fh = frame.height as usize;
         let delay = frame.delay;
         let dispose = frame.dispose;
+        let needs_user_input = frame.needs_user_input;
 
         // Snapshot canvas BEFORE compositing for DisposalMethod::Previous
         let pre_composite = if dispose == gif::DisposalMethod::Previous {
@@ -145,46 +474,46 @@ fn decode_all_frames(input_path: &Path) -> Result<(u16, u16, gif::Repeat, Vec<De
         let rgba_buf = &frame.buffer;
         for row in 0..fh {
             let cy = ft + row;
-            if cy >= h as usize {
+            if cy >= h {
                 break;
             }
             for col in 0..fw {
                 let cx = fl + col;
-                if cx >= w as usize {
+                if cx >= w {
                     break;
                 }
                 let src_idx = (row * fw + col) * 4;
-                let dst_idx = (cy * w as usize + cx) * 4;
+                let dst_idx = (cy * w + cx) * 4;
                 let alpha = rgba_buf[src_idx + 3];
                 if alpha > 0 {
-                    canvas[dst_idx..dst_idx + 4]
-                        .copy_from_slice(&rgba_buf[src_idx..src_idx + 4]);
+                    canvas[dst_idx..dst_idx + 4].copy_from_slice(&rgba_buf[src_idx..src_idx + 4]);
                 }
             }
         }
 
         // Snapshot the current canvas as this frame's full image
-        frames.push(DecodedFrame {
+        let decoded = DecodedFrame {
             canvas: canvas.clone(),
             delay,
-        });
+            needs_user_input,
+        };
 
         // Handle disposal for the NEXT frame's base
         match dispose {
             gif::DisposalMethod::Background => {
-                // Fill sub-frame region with GIF logical background color
+                // Clear sub-frame region to transparent, as browsers do
                 for row in 0..fh {
                     let cy = ft + row;
-                    if cy >= h as usize {
+                    if cy >= h {
                         break;
                     }
                     for col in 0..fw {
                         let cx = fl + col;
-                        if cx >= w as usize {
+                        if cx >= w {
                             break;
                         }
-                        let idx = (cy * w as usize + cx) * 4;
-                        canvas[idx..idx + 4].copy_from_slice(&bg_fill);
+                        let idx = (cy * w + cx) * 4;
+                        canvas[idx..idx + 4].fill(0);
                     }
                 }
             }
@@ -193,147 +522,506 @@ fn decode_all_frames(input_path: &Path) -> Result<(u16, u16, gif::Repeat, Vec<De
                 if let Some(prev) = pre_composite {
                     canvas.copy_from_slice(&prev);
                 } else {
-                    for px in canvas.chunks_exact_mut(4) {
-                        px.copy_from_slice(&bg_fill);
-                    }
+                    canvas.fill(0);
                 }
             }
             _ => {
                 // Keep / Any — canvas stays as-is
             }
         }
-    }
 
-    Ok((w, h, repeat, frames))
+        Ok(Some(decoded))
+    }
 }
 
 // ---------------------------------------------------------------------------
-// Phase 2: Delta computation
+// Phase 2: Frame filtering and delta computation
 // ---------------------------------------------------------------------------
 
-fn compute_delta_frames(
-    decoded: &[DecodedFrame],
-    width: u16,
-    height: u16,
-    is_single_frame: bool,
-) -> Vec<DeltaFrame> {
-    let w = width as usize;
-    let h = height as usize;
-
-    decoded
-        .iter()
-        .enumerate()
-        .map(|(i, frame)| {
-            if i == 0 || is_single_frame {
-                // First frame (or single-frame GIF): encode entire canvas
-                DeltaFrame {
-                    rgba: frame.canvas.clone(),
-                    width,
-                    height,
-                    left: 0,
-                    top: 0,
-                    delay: frame.delay,
-                    dispose: gif::DisposalMethod::Keep,
-                }
-            } else {
-                let prev = &decoded[i - 1].canvas;
-                let curr = &frame.canvas;
-
-                // Find bounding box of changed pixels
-                let mut min_x = w;
-                let mut min_y = h;
-                let mut max_x: usize = 0;
-                let mut max_y: usize = 0;
-
-                for y in 0..h {
-                    for x in 0..w {
-                        let idx = (y * w + x) * 4;
-                        if prev[idx..idx + 4] != curr[idx..idx + 4] {
-                            min_x = min_x.min(x);
-                            min_y = min_y.min(y);
-                            max_x = max_x.max(x);
-                            max_y = max_y.max(y);
-                        }
+/// Turns decoded frames into delta frames one at a time, so only a few
+/// canvases are alive however many frames the animation has.
+struct FrameStream {
+    decoder: FrameDecoder,
+    tolerance: u8,
+    /// Delay a kept frame must reach before the next frame is shown; 0 = unlimited.
+    min_interval: u16,
+    speed: f32,
+    min_delay: u16,
+    /// Last kept frame; following frames may still fold into it.
+    kept: Option<DecodedFrame>,
+    /// The canvas the last delta frame was composited onto, as a decoder sees it.
+    base: Vec<u8>,
+    /// Full canvas of the last delta frame.
+    shown: Option<Vec<u8>>,
+    /// Last delta frame; its disposal is chosen once the next frame is known.
+    pending: Option<DeltaFrame>,
+    finished: bool,
+}
+
+impl FrameStream {
+    fn open(
+        input_path: &Path,
+        params: &QualityParams,
+        options: &GifOptions,
+    ) -> Result<Self, String> {
+        let decoder = FrameDecoder::open(input_path)?;
+        let canvas_size = decoder.width as usize * decoder.height as usize * 4;
+        // GIF delays are in centiseconds
+        let min_interval = match options.max_fps {
+            0 => 0,
+            fps => 100u32.div_ceil(fps).min(u16::MAX as u32) as u16,
+        };
+        Ok(Self {
+            decoder,
+            tolerance: params.lossy_tolerance,
+            min_interval,
+            speed: options.speed,
+            min_delay: options.min_delay,
+            kept: None,
+            base: vec![0u8; canvas_size],
+            shown: None,
+            pending: None,
+            finished: false,
+        })
+    }
+
+    /// The next kept frame as a full canvas, or `None` after the last one.
+    fn next_canvas(&mut self) -> Result<Option<DecodedFrame>, String> {
+        while !self.finished {
+            match self.decoder.next_frame()? {
+                Some(frame) => {
+                    if let Some(kept) = self.keep(frame) {
+                        return Ok(Some(kept));
                     }
                 }
+                None => self.finished = true,
+            }
+        }
+        Ok(self.kept.take())
+    }
 
-                if min_x > max_x || min_y > max_y {
-                    // No change — emit a 1x1 transparent frame
-                    DeltaFrame {
-                        rgba: vec![0, 0, 0, 0],
-                        width: 1,
-                        height: 1,
-                        left: 0,
-                        top: 0,
-                        delay: frame.delay,
-                        dispose: gif::DisposalMethod::Keep,
-                    }
-                } else {
-                    let dw = max_x - min_x + 1;
-                    let dh = max_y - min_y + 1;
-                    let mut delta_rgba = vec![0u8; dw * dh * 4];
-
-                    for dy in 0..dh {
-                        for dx in 0..dw {
-                            let sx = min_x + dx;
-                            let sy = min_y + dy;
-                            let src_idx = (sy * w + sx) * 4;
-                            let dst_idx = (dy * dw + dx) * 4;
-
-                            if prev[src_idx..src_idx + 4] == curr[src_idx..src_idx + 4] {
-                                // Unchanged pixel → transparent
-                                delta_rgba[dst_idx..dst_idx + 4]
-                                    .copy_from_slice(&[0, 0, 0, 0]);
-                            } else {
-                                delta_rgba[dst_idx..dst_idx + 4]
-                                    .copy_from_slice(&curr[src_idx..src_idx + 4]);
-                            }
-                        }
-                    }
+    /// The next finished delta frame, or `None` after the last one.
+    fn next_delta(&mut self) -> Result<Option<DeltaFrame>, String> {
+        while let Some(frame) = self.next_canvas()? {
+            if let Some(delta) = self.push_delta(frame) {
+                return Ok(Some(delta));
+            }
+        }
+        Ok(self.pending.take())
+    }
 
-                    DeltaFrame {
-                        rgba: delta_rgba,
-                        width: dw as u16,
-                        height: dh as u16,
-                        left: min_x as u16,
-                        top: min_y as u16,
-                        delay: frame.delay,
-                        dispose: gif::DisposalMethod::Keep,
-                    }
+    /// Fold `frame` into the last kept frame, adding its delay, when it
+    /// repeats it (common in screen recordings; every channel within
+    /// `tolerance`) or starts before the last kept frame has been on screen
+    /// for 1/`max_fps` seconds. Returns the previous kept frame once it's final.
+    fn keep(&mut self, mut frame: DecodedFrame) -> Option<DecodedFrame> {
+        // Retime first so the frame rate limit applies to the output timing
+        frame.delay = retime(frame.delay, self.speed, self.min_delay);
+        if let Some(last) = self.kept.as_mut() {
+            if canvases_match(&last.canvas, &frame.canvas, self.tolerance)
+                || effective_delay(last.delay) < self.min_interval
+            {
+                last.delay = combined_delay(last.delay, frame.delay);
+                last.needs_user_input |= frame.needs_user_input;
+                return None;
+            }
+        }
+        self.kept.replace(frame)
+    }
+
+    /// Queue `frame` as a delta and return the previous delta, now that its
+    /// disposal is decided.
+    fn push_delta(&mut self, frame: DecodedFrame) -> Option<DeltaFrame> {
+        let w = self.decoder.width as usize;
+        let h = self.decoder.height as usize;
+
+        let Some(shown) = self.shown.take() else {
+            // First frame: encode entire canvas
+            self.pending = Some(DeltaFrame {
+                rgba: frame.canvas.clone(),
+                width: self.decoder.width,
+                height: self.decoder.height,
+                left: 0,
+                top: 0,
+                delay: frame.delay,
+                dispose: gif::DisposalMethod::Keep,
+                needs_user_input: frame.needs_user_input,
+            });
+            self.shown = Some(frame.canvas);
+            return None;
+        };
+
+        // The previous frame's disposal decides what this frame is drawn
+        // over. Try each and keep the one needing the smallest rectangle.
+        let prev = self.pending.as_mut().expect("shown frame is pending");
+        let mut best: Option<(usize, gif::DisposalMethod, Vec<u8>)> = None;
+        for dispose in [
+            gif::DisposalMethod::Keep,
+            gif::DisposalMethod::Previous,
+            gif::DisposalMethod::Background,
+        ] {
+            let candidate = match dispose {
+                gif::DisposalMethod::Previous => self.base.clone(),
+                gif::DisposalMethod::Background => {
+                    let mut cleared = shown.clone();
+                    clear_rect(&mut cleared, w, prev);
+                    cleared
                 }
+                _ => shown.clone(),
+            };
+            let Some(cost) = delta_cost(&candidate, &frame.canvas, w, h) else {
+                continue;
+            };
+            if best
+                .as_ref()
+                .is_none_or(|(best_cost, ..)| cost < *best_cost)
+            {
+                best = Some((cost, dispose, candidate));
             }
-        })
-        .collect()
+        }
+
+        // No disposal can reproduce the frame exactly (pixels turning
+        // transparent outside the previous rectangle); keep the old behavior
+        let (dispose, next_base) = match best {
+            Some((_, dispose, candidate)) => (dispose, candidate),
+            None => (gif::DisposalMethod::Keep, shown),
+        };
+        prev.dispose = dispose;
+        self.base = next_base;
+
+        let delta = delta_against(&self.base, &frame, w, h);
+        self.shown = Some(frame.canvas);
+        self.pending.replace(delta)
+    }
+}
+
+/// Browsers play delays under 2 cs at 10 cs; going by the raw value would
+/// merge a whole "0-delay" animation into one frame.
+fn effective_delay(delay: u16) -> u16 {
+    if delay < 2 {
+        10
+    } else {
+        delay
+    }
+}
+
+/// Scale a delay by the playback speed and raise it to `min_delay`. Any
+/// change starts from the delay browsers play and stays at 2 cs or more,
+/// which they would otherwise slow down to 10.
+fn retime(delay: u16, speed: f32, min_delay: u16) -> u16 {
+    if speed == 1.0 && min_delay == 0 {
+        return delay;
+    }
+    let speed = if speed > 0.0 { speed } else { 1.0 };
+    let scaled = (effective_delay(delay) as f32 / speed)
+        .round()
+        .min(u16::MAX as f32) as u16;
+    scaled.max(min_delay).max(2)
+}
+
+/// Delay of one frame that replaces two, as long as both played.
+fn combined_delay(a: u16, b: u16) -> u16 {
+    effective_delay(a).saturating_add(effective_delay(b))
+}
+
+fn canvases_match(a: &[u8], b: &[u8], tolerance: u8) -> bool {
+    if tolerance == 0 {
+        return a == b;
+    }
+    a.chunks_exact(4)
+        .zip(b.chunks_exact(4))
+        .all(|(pa, pb)| pa[3] == pb[3] && (0..3).all(|ch| pa[ch].abs_diff(pb[ch]) <= tolerance))
+}
+
+/// Bounding box `(min_x, min_y, max_x, max_y)` of the pixels that differ.
+fn changed_bounds(
+    base: &[u8],
+    curr: &[u8],
+    w: usize,
+    h: usize,
+) -> Option<(usize, usize, usize, usize)> {
+    let mut min_x = w;
+    let mut min_y = h;
+    let mut max_x: usize = 0;
+    let mut max_y: usize = 0;
+
+    for y in 0..h {
+        for x in 0..w {
+            let idx = (y * w + x) * 4;
+            if base[idx..idx + 4] != curr[idx..idx + 4] {
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+
+    (min_x <= max_x && min_y <= max_y).then_some((min_x, min_y, max_x, max_y))
+}
+
+/// Area of the rectangle needed to turn `base` into `curr`, or `None` when a
+/// delta can't do it: transparent pixels in a frame leave the base showing,
+/// so a pixel that has to become transparent can't be drawn.
+fn delta_cost(base: &[u8], curr: &[u8], w: usize, h: usize) -> Option<usize> {
+    let turns_transparent = base
+        .chunks_exact(4)
+        .zip(curr.chunks_exact(4))
+        .any(|(b, c)| c[3] == 0 && b != c);
+    if turns_transparent {
+        return None;
+    }
+    Some(
+        changed_bounds(base, curr, w, h)
+            .map(|(min_x, min_y, max_x, max_y)| (max_x - min_x + 1) * (max_y - min_y + 1))
+            .unwrap_or(0),
+    )
+}
+
+/// What `DisposalMethod::Background` does: the frame's rectangle becomes transparent.
+fn clear_rect(canvas: &mut [u8], w: usize, frame: &DeltaFrame) {
+    for y in frame.top as usize..(frame.top + frame.height) as usize {
+        let start = (y * w + frame.left as usize) * 4;
+        canvas[start..start + frame.width as usize * 4].fill(0);
+    }
+}
+
+fn delta_against(base: &[u8], frame: &DecodedFrame, w: usize, h: usize) -> DeltaFrame {
+    let curr = &frame.canvas;
+    let Some((min_x, min_y, max_x, max_y)) = changed_bounds(base, curr, w, h) else {
+        // No change — emit a 1x1 transparent frame
+        return DeltaFrame {
+            rgba: vec![0, 0, 0, 0],
+            width: 1,
+            height: 1,
+            left: 0,
+            top: 0,
+            delay: frame.delay,
+            dispose: gif::DisposalMethod::Keep,
+            needs_user_input: frame.needs_user_input,
+        };
+    };
+
+    let dw = max_x - min_x + 1;
+    let dh = max_y - min_y + 1;
+    let mut delta_rgba = vec![0u8; dw * dh * 4];
+
+    for dy in 0..dh {
+        for dx in 0..dw {
+            let src_idx = ((min_y + dy) * w + min_x + dx) * 4;
+            let dst_idx = (dy * dw + dx) * 4;
+            // Unchanged pixels stay transparent
+            if base[src_idx..src_idx + 4] != curr[src_idx..src_idx + 4] {
+                delta_rgba[dst_idx..dst_idx + 4].copy_from_slice(&curr[src_idx..src_idx + 4]);
+            }
+        }
+    }
+
+    DeltaFrame {
+        rgba: delta_rgba,
+        width: dw as u16,
+        height: dh as u16,
+        left: min_x as u16,
+        top: min_y as u16,
+        delay: frame.delay,
+        dispose: gif::DisposalMethod::Keep,
+        needs_user_input: frame.needs_user_input,
+    }
 }
 
 // ---------------------------------------------------------------------------
-// Phase 3a: Parallel encoding
+// Shared global palette
 // ---------------------------------------------------------------------------
 
-fn encode_frames_parallel(
-    delta_frames: &[DeltaFrame],
+/// One palette for the whole animation, written as the global color table.
+struct GlobalPalette {
+    palette: Vec<imagequant::RGBA>,
+    transparent: u8,
+    /// Frames remapped with a larger error get a local palette instead.
+    max_mse: f64,
+}
+
+/// Quantize one histogram built from every frame, in a decoding pass of its
+/// own. Also returns the number of output frames, which APNG declares up front.
+fn build_global_palette(
+    input_path: &Path,
     quality: u32,
     params: &QualityParams,
-) -> Vec<gif::Frame<'static>> {
-    delta_frames
-        .par_iter()
-        .map(|df| encode_single_frame(df, quality, params))
-        .collect()
+    options: &GifOptions,
+) -> Result<(Option<GlobalPalette>, usize), String> {
+    let mut liq = imagequant::new();
+    // Reserve one palette slot for transparency → max 255 colors
+    let configured = liq.set_quality(0, quality as u8).is_ok() && liq.set_max_colors(255).is_ok();
+
+    let mut histogram = configured.then(|| imagequant::Histogram::new(&liq));
+    let mut frames = 0;
+    let mut stream = FrameStream::open(input_path, params, options)?;
+    while let Some(df) = stream.next_delta()? {
+        frames += 1;
+        if let Some(h) = histogram.as_mut() {
+            if add_to_histogram(h, &liq, &df, params).is_none() {
+                histogram = None;
+            }
+        }
+    }
+
+    let global = histogram.and_then(|mut histogram| {
+        let mut res = histogram.quantize(&liq).ok()?;
+        let mut palette = res.palette_vec();
+        let transparent = normalize_transparency(&mut palette, &mut []).unwrap_or_else(|| {
+            palette.insert(0, imagequant::RGBA::new(0, 0, 0, 0));
+            0
+        });
+        Some(GlobalPalette {
+            palette,
+            transparent,
+            max_mse: quality_to_mse(quality),
+        })
+    });
+    Ok((global, frames))
+}
+
+fn add_to_histogram(
+    histogram: &mut imagequant::Histogram,
+    liq: &imagequant::Attributes,
+    df: &DeltaFrame,
+    params: &QualityParams,
+) -> Option<()> {
+    let (fw, fh) = (df.width as usize, df.height as usize);
+    let rgba = if params.lossy_tolerance > 0 {
+        lossy_pixel_align(&df.rgba, fw, fh, params.lossy_tolerance)
+    } else {
+        df.rgba.clone()
+    };
+    let pixels: &[imagequant::RGBA] =
+        unsafe { std::slice::from_raw_parts(rgba.as_ptr() as *const imagequant::RGBA, fw * fh) };
+    let mut img = liq.new_image_borrowed(pixels, fw, fh, 0.0).ok()?;
+    histogram.add_image(liq, &mut img).ok()
+}
+
+/// libimagequant's quality → MSE curve, scaled from its 0–1 color range to
+/// 0–255 channel values.
+fn quality_to_mse(quality: u32) -> f64 {
+    let q = quality as f64;
+    let extra_low_quality_fudge = (0.016 / (0.001 + q) - 0.001).max(0.0);
+    (extra_low_quality_fudge + 2.5 / (210.0 + q).powf(1.2) * (100.1 - q) / 100.0) * 255.0 * 255.0
+}
+
+/// Map a frame onto the global palette with Floyd–Steinberg dithering.
+/// Returns the palette indices and the mean squared error over opaque pixels.
+fn remap_to_global(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    global: &GlobalPalette,
+    dithering: f32,
+) -> (Vec<u8>, f64) {
+    let mut indices = vec![global.transparent; width * height];
+    let mut cache: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut nearest = |rgb: [u8; 3]| {
+        *cache
+            .entry(rgb)
+            .or_insert_with(|| nearest_opaque(&global.palette, rgb))
+    };
+    // Diffused error for the current and the next row, RGB per pixel
+    let mut err_curr = vec![[0f32; 3]; width + 2];
+    let mut err_next = vec![[0f32; 3]; width + 2];
+    let mut sq_error = 0f64;
+    let mut opaque = 0usize;
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            let px = &rgba[i * 4..i * 4 + 4];
+            if px[3] == 0 {
+                continue;
+            }
+
+            // Judge the palette by the undithered match, as imagequant does;
+            // dithering noise would inflate the error of every gradient
+            let plain = nearest([px[0], px[1], px[2]]);
+            let c = global.palette[plain as usize];
+            sq_error += [c.r, c.g, c.b]
+                .iter()
+                .zip(px)
+                .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+                .sum::<f64>();
+            opaque += 1;
+
+            let e = err_curr[x + 1];
+            let target = [
+                (px[0] as f32 + e[0]).round().clamp(0.0, 255.0) as u8,
+                (px[1] as f32 + e[1]).round().clamp(0.0, 255.0) as u8,
+                (px[2] as f32 + e[2]).round().clamp(0.0, 255.0) as u8,
+            ];
+            let idx = nearest(target);
+            indices[i] = idx;
+
+            let c = global.palette[idx as usize];
+            let chosen = [c.r, c.g, c.b];
+            for ch in 0..3 {
+                let quant_error = (target[ch] as f32 - chosen[ch] as f32) * dithering;
+                err_curr[x + 2][ch] += quant_error * 7.0 / 16.0;
+                err_next[x][ch] += quant_error * 3.0 / 16.0;
+                err_next[x + 1][ch] += quant_error * 5.0 / 16.0;
+                err_next[x + 2][ch] += quant_error / 16.0;
+            }
+        }
+        std::mem::swap(&mut err_curr, &mut err_next);
+        err_next.iter_mut().for_each(|e| *e = [0.0; 3]);
+    }
+
+    let mse = if opaque == 0 {
+        0.0
+    } else {
+        sq_error / (opaque * 3) as f64
+    };
+    (indices, mse)
+}
+
+fn nearest_opaque(palette: &[imagequant::RGBA], rgb: [u8; 3]) -> u8 {
+    let mut best = (0usize, u32::MAX);
+    for (i, c) in palette.iter().enumerate() {
+        if c.a == 0 {
+            continue;
+        }
+        let dr = c.r as i32 - rgb[0] as i32;
+        let dg = c.g as i32 - rgb[1] as i32;
+        let db = c.b as i32 - rgb[2] as i32;
+        let dist = (dr * dr + dg * dg + db * db) as u32;
+        if dist < best.1 {
+            best = (i, dist);
+        }
+    }
+    best.0 as u8
+}
+
+fn palette_bytes(palette: &[imagequant::RGBA]) -> Vec<u8> {
+    palette.iter().flat_map(|c| [c.r, c.g, c.b]).collect()
 }
 
 // ---------------------------------------------------------------------------
-// Phase 3b: Sequential encoding (fallback for large GIFs)
+// Phase 3: Batched encoding
 // ---------------------------------------------------------------------------
 
-fn encode_frames_sequential(
-    delta_frames: &[DeltaFrame],
+/// Quantize and LZW-encode a batch of frames, in parallel when there are several.
+fn encode_batch(
+    batch: &[DeltaFrame],
     quality: u32,
     params: &QualityParams,
+    global: Option<&GlobalPalette>,
 ) -> Vec<gif::Frame<'static>> {
-    delta_frames
-        .iter()
-        .map(|df| encode_single_frame(df, quality, params))
-        .collect()
+    if batch.len() > 1 {
+        batch
+            .par_iter()
+            .map(|df| encode_single_frame(df, quality, params, global))
+            .collect()
+    } else {
+        batch
+            .iter()
+            .map(|df| encode_single_frame(df, quality, params, global))
+            .collect()
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -344,6 +1032,7 @@ fn encode_single_frame(
     df: &DeltaFrame,
     quality: u32,
     params: &QualityParams,
+    global: Option<&GlobalPalette>,
 ) -> gif::Frame<'static> {
     let fw = df.width as usize;
     let fh = df.height as usize;
@@ -355,6 +1044,28 @@ fn encode_single_frame(
         df.rgba.clone()
     };
 
+    // Use the global color table when it represents this frame well enough
+    if let Some(global) = global {
+        let (pixels, mse) = remap_to_global(&rgba_input, fw, fh, global, params.dithering);
+        if mse <= global.max_mse {
+            let mut frame = gif::Frame {
+                width: df.width,
+                height: df.height,
+                left: df.left,
+                top: df.top,
+                delay: df.delay,
+                dispose: df.dispose,
+                needs_user_input: df.needs_user_input,
+                transparent: Some(global.transparent),
+                buffer: std::borrow::Cow::Owned(pixels),
+                ..Default::default()
+            };
+            let palette = palette_bytes(&global.palette);
+            lzw_pre_encode(&mut frame, &palette, params.lzw_tolerance);
+            return frame;
+        }
+    }
+
     // Try imagequant quantization
     if let Some((palette, pixels, transparent_idx)) =
         quantize_frame(&rgba_input, fw, fh, quality, params.dithering)
@@ -366,17 +1077,20 @@ fn encode_single_frame(
             palette_bytes.push(c.b);
         }
 
-        let mut frame = gif::Frame::default();
-        frame.width = df.width;
-        frame.height = df.height;
-        frame.left = df.left;
-        frame.top = df.top;
-        frame.delay = df.delay;
-        frame.dispose = df.dispose;
+        let mut frame = gif::Frame {
+            width: df.width,
+            height: df.height,
+            left: df.left,
+            top: df.top,
+            delay: df.delay,
+            dispose: df.dispose,
+            needs_user_input: df.needs_user_input,
+            transparent: transparent_idx,
+            buffer: std::borrow::Cow::Owned(pixels),
+            ..Default::default()
+        };
+        lzw_pre_encode(&mut frame, &palette_bytes, params.lzw_tolerance);
         frame.palette = Some(palette_bytes);
-        frame.transparent = transparent_idx;
-        frame.buffer = std::borrow::Cow::Owned(pixels);
-        frame.make_lzw_pre_encoded();
         frame
     } else {
         // Fallback: use gif crate's built-in RGBA → indexed conversion
@@ -386,6 +1100,7 @@ fn encode_single_frame(
         frame.top = df.top;
         frame.delay = df.delay;
         frame.dispose = df.dispose;
+        frame.needs_user_input = df.needs_user_input;
         frame.make_lzw_pre_encoded();
         frame
     }
@@ -420,15 +1135,35 @@ fn quantize_frame(
     let mut res = liq.quantize(&mut img).ok()?;
     res.set_dithering_level(dithering).ok()?;
 
-    let (palette, pixels) = res.remapped(&mut img).ok()?;
-
-    // Find existing transparent entry, or the slot is implicitly available
-    // because we limited max_colors to 255
-    let transparent_idx = palette.iter().position(|c| c.a == 0).map(|i| i as u8);
+    let (mut palette, mut pixels) = res.remapped(&mut img).ok()?;
+    let transparent_idx = normalize_transparency(&mut palette, &mut pixels);
 
     Some((palette, pixels, transparent_idx))
 }
 
+/// GIF has a single fully transparent index per frame. Fold every palette
+/// entry that is mostly transparent into index 0, so all frames agree on it,
+/// and return it; `None` when nothing is transparent.
+fn normalize_transparency(palette: &mut Vec<imagequant::RGBA>, pixels: &mut [u8]) -> Option<u8> {
+    if palette.iter().all(|c| c.a >= 128) {
+        return None;
+    }
+
+    let mut remap = [0u8; 256];
+    let mut normalized = vec![imagequant::RGBA::new(0, 0, 0, 0)];
+    for (i, c) in palette.iter().enumerate() {
+        if c.a >= 128 {
+            remap[i] = normalized.len() as u8;
+            normalized.push(*c);
+        }
+    }
+    for px in pixels.iter_mut() {
+        *px = remap[*px as usize];
+    }
+    *palette = normalized;
+    Some(0)
+}
+
 // ---------------------------------------------------------------------------
 // Lossy pixel alignment: merge similar adjacent pixels for better LZW runs
 // ---------------------------------------------------------------------------
@@ -463,33 +1198,687 @@ fn lossy_pixel_align(rgba: &[u8], width: usize, height: usize, tolerance: u8) ->
     out
 }
 
+// ---------------------------------------------------------------------------
+// Lossy LZW: substitute near-identical colors to extend dictionary matches
+// ---------------------------------------------------------------------------
+
+/// GIF codes are at most 12 bits.
+const LZW_MAX_CODES: u16 = 4096;
+
+/// LZW-compress the frame's index buffer in place, in the format
+/// `write_lzw_pre_encoded_frame` expects.
+fn lzw_pre_encode(frame: &mut gif::Frame<'static>, palette: &[u8], tolerance: u16) {
+    if tolerance == 0 {
+        frame.make_lzw_pre_encoded();
+        return;
+    }
+    let encoded = lzw_encode_lossy(&frame.buffer, palette, frame.transparent, tolerance);
+    frame.buffer = std::borrow::Cow::Owned(encoded);
+}
+
+/// LZW encoder that, like gifsicle's `--lossy`, follows a dictionary entry
+/// whose next color is within `tolerance` of the actual pixel when there is
+/// no exact continuation. Longer matches mean fewer codes, at the cost of
+/// small color errors the decoder reproduces from the dictionary.
+///
+/// Returns the minimum code size byte followed by the raw LSB-first code stream.
+fn lzw_encode_lossy(
+    indices: &[u8],
+    palette: &[u8],
+    transparent: Option<u8>,
+    tolerance: u16,
+) -> Vec<u8> {
+    let max_index = indices.iter().copied().max().unwrap_or(0);
+    // Same sizing as the gif crate: at least 2 bits
+    let min_code_size = (max_index as u32 + 1)
+        .max(4)
+        .next_power_of_two()
+        .trailing_zeros() as u8;
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let max_distance = tolerance as u32 * tolerance as u32;
+
+    let distance = |a: u8, b: u8| -> u32 {
+        if a == b {
+            return 0;
+        }
+        // Transparency is never traded for a color, or the other way round
+        if Some(a) == transparent || Some(b) == transparent {
+            return u32::MAX;
+        }
+        let (a, b) = (a as usize * 3, b as usize * 3);
+        match (palette.get(a..a + 3), palette.get(b..b + 3)) {
+            (Some(ca), Some(cb)) => ca
+                .iter()
+                .zip(cb)
+                .map(|(&x, &y)| (x as i32 - y as i32).pow(2) as u32)
+                .sum(),
+            _ => u32::MAX,
+        }
+    };
+
+    // Trie: children[code] lists (next index, code of the extended string)
+    let mut children: Vec<Vec<(u8, u16)>> = vec![Vec::new(); LZW_MAX_CODES as usize];
+    let mut next_code = clear + 2;
+    let mut code_size = min_code_size + 1;
+
+    let mut out = BitWriter::default();
+    out.buf.push(min_code_size);
+    out.write(clear, code_size);
+
+    let mut pos = 0;
+    while pos < indices.len() {
+        // The first index is matched exactly; it starts the decoder's next entry
+        let mut code = indices[pos] as u16;
+        pos += 1;
+        while let Some(&pixel) = indices.get(pos) {
+            let mut best: Option<(u32, u16)> = None;
+            for &(index, child) in &children[code as usize] {
+                let d = distance(index, pixel);
+                if d <= max_distance && best.is_none_or(|(bd, _)| d < bd) {
+                    best = Some((d, child));
+                }
+            }
+            let Some((_, child)) = best else {
+                break;
+            };
+            code = child;
+            pos += 1;
+        }
+        out.write(code, code_size);
+
+        let Some(&next) = indices.get(pos) else {
+            break;
+        };
+        if next_code < LZW_MAX_CODES {
+            children[code as usize].push((next, next_code));
+            next_code += 1;
+            // The decoder adds each entry one code later, so it widens once
+            // the newest entry no longer fits
+            if next_code > (1 << code_size) && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            out.write(clear, code_size);
+            children.iter_mut().for_each(Vec::clear);
+            next_code = clear + 2;
+            code_size = min_code_size + 1;
+        }
+    }
+
+    // Reading the last code still adds an entry on the decoder's side, which
+    // widens the end code if the table just filled the current width
+    if next_code == 1 << code_size && code_size < 12 {
+        code_size += 1;
+    }
+    out.write(end, code_size);
+    out.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    buf: Vec<u8>,
+    acc: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.buf.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.buf.push(self.acc as u8);
+        }
+        self.buf
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Phase 4: Write the GIF
 // ---------------------------------------------------------------------------
 
-fn write_gif(
+fn create_encoder(
     output_path: &Path,
     width: u16,
     height: u16,
     repeat: gif::Repeat,
-    frames: &[gif::Frame<'static>],
-) -> Result<(), String> {
-    let mut output = std::fs::File::create(output_path)
+    global_palette: &[u8],
+    extensions: &[RawExtension],
+) -> Result<gif::Encoder<std::fs::File>, String> {
+    let output = std::fs::File::create(output_path)
         .map_err(|e| format!("GIF 출력 파일 생성 실패: {}", e))?;
 
-    let mut encoder = gif::Encoder::new(&mut output, width, height, &[])
+    let mut encoder = gif::Encoder::new(output, width, height, global_palette)
         .map_err(|e| format!("GIF 인코더 생성 실패: {}", e))?;
 
     encoder
         .set_repeat(repeat)
         .map_err(|e| format!("GIF 반복 설정 실패: {}", e))?;
 
-    for frame in frames {
+    for ext in extensions {
+        let blocks: Vec<&[u8]> = ext.blocks.iter().map(Vec::as_slice).collect();
         encoder
-            .write_lzw_pre_encoded_frame(frame)
-            .map_err(|e| format!("GIF 프레임 쓰기 실패: {}", e))?;
+            .write_raw_extension(gif::AnyExtension(ext.label), &blocks)
+            .map_err(|e| format!("GIF 확장 블록 쓰기 실패: {}", e))?;
     }
 
-    drop(encoder);
-    Ok(())
+    Ok(encoder)
+}
+
+// ---------------------------------------------------------------------------
+// Conversion: animated WebP
+// ---------------------------------------------------------------------------
+
+/// Hand the full canvases to libwebp's animation encoder, which picks its own
+/// sub-rectangles and blending. Frames are added as they are decoded.
+fn encode_webp(
+    input_path: &Path,
+    output_path: &Path,
+    options: &CompressOptions,
+    params: &QualityParams,
+    source: &SourceInfo,
+    progress: Progress,
+) -> Result<(), String> {
+    let lossless = options.gif.target == GifTarget::WebpLossless;
+    let config = webp_config(options.quality, lossless)?;
+
+    let mut stream = FrameStream::open(input_path, params, &options.gif)?;
+    let plays = total_plays(output_repeat(&options.gif, stream.decoder.repeat));
+    let mut encoder = WebpAnimEncoder::new(
+        stream.decoder.width as i32,
+        stream.decoder.height as i32,
+        plays,
+        !lossless,
+    )?;
+
+    let total = source.frames.max(1) as f32;
+    let mut timestamp: i32 = 0;
+    while let Some(frame) = stream.next_canvas()? {
+        encoder.add(&frame.canvas, timestamp, &config)?;
+        // WebP durations are in milliseconds; keep what browsers showed for the GIF
+        timestamp += effective_delay(frame.delay) as i32 * 10;
+        progress(Stage::Encode, stream.decoder.frames_read as f32 / total);
+    }
+
+    let data = encoder.assemble(timestamp)?;
+    let data = add_webp_metadata(data, source)?;
+    std::fs::write(output_path, data).map_err(|e| format!("WebP 저장 실패: {}", e))
+}
+
+fn webp_config(quality: u32, lossless: bool) -> Result<webp::WebPConfig, String> {
+    let mut config = std::mem::MaybeUninit::<webp::WebPConfig>::uninit();
+    unsafe {
+        let ok = webp::WebPConfigInitInternal(
+            config.as_mut_ptr(),
+            webp::WebPPreset::WEBP_PRESET_DEFAULT,
+            quality.min(100) as f32,
+            webp::WEBP_ENCODER_ABI_VERSION as i32,
+        );
+        if ok == 0 {
+            return Err("WebP 설정 초기화 실패".to_string());
+        }
+        let mut config = config.assume_init();
+        if lossless && webp::WebPConfigLosslessPreset(&mut config, 6) == 0 {
+            return Err("WebP 설정 초기화 실패".to_string());
+        }
+        Ok(config)
+    }
+}
+
+/// Owns a libwebp `WebPAnimEncoder`.
+struct WebpAnimEncoder {
+    ptr: *mut webp::WebPAnimEncoder,
+    width: i32,
+    height: i32,
+}
+
+impl WebpAnimEncoder {
+    fn new(width: i32, height: i32, plays: u32, allow_mixed: bool) -> Result<Self, String> {
+        let mut options = std::mem::MaybeUninit::<webp::WebPAnimEncoderOptions>::uninit();
+        unsafe {
+            if webp::WebPAnimEncoderOptionsInitInternal(
+                options.as_mut_ptr(),
+                webp::WebPGetMuxABIVersion(),
+            ) == 0
+            {
+                return Err("WebP 인코더 생성 실패".to_string());
+            }
+            let mut options = options.assume_init();
+            options.anim_params.loop_count = plays.min(u16::MAX as u32) as i32;
+            // Transparent, like the GIF background
+            options.anim_params.bgcolor = 0;
+            // Lets each lossy frame fall back to lossless where that's smaller
+            options.allow_mixed = allow_mixed as i32;
+
+            let ptr = webp::WebPAnimEncoderNewInternal(
+                width,
+                height,
+                &options,
+                webp::WebPGetMuxABIVersion(),
+            );
+            if ptr.is_null() {
+                return Err("WebP 인코더 생성 실패".to_string());
+            }
+            Ok(Self { ptr, width, height })
+        }
+    }
+
+    fn add(
+        &mut self,
+        rgba: &[u8],
+        timestamp_ms: i32,
+        config: &webp::WebPConfig,
+    ) -> Result<(), String> {
+        let mut picture = std::mem::MaybeUninit::<webp::WebPPicture>::uninit();
+        unsafe {
+            if webp::WebPPictureInitInternal(
+                picture.as_mut_ptr(),
+                webp::WEBP_ENCODER_ABI_VERSION as i32,
+            ) == 0
+            {
+                return Err("WebP 프레임 인코딩 실패".to_string());
+            }
+            let mut picture = picture.assume_init();
+            picture.use_argb = 1;
+            picture.width = self.width;
+            picture.height = self.height;
+
+            // The encoder copies the frame, so the picture can go right away
+            let added = webp::WebPPictureImportRGBA(&mut picture, rgba.as_ptr(), self.width * 4)
+                != 0
+                && webp::WebPAnimEncoderAdd(self.ptr, &mut picture, timestamp_ms, config) != 0;
+            webp::WebPPictureFree(&mut picture);
+            if !added {
+                return Err(format!("WebP 프레임 인코딩 실패: {}", self.error()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the animation at `end_ms`, the end of the last frame.
+    fn assemble(self, end_ms: i32) -> Result<Vec<u8>, String> {
+        unsafe {
+            if webp::WebPAnimEncoderAdd(self.ptr, std::ptr::null_mut(), end_ms, std::ptr::null())
+                == 0
+            {
+                return Err(format!("WebP 인코딩 실패: {}", self.error()));
+            }
+            let mut data: webp::WebPData = std::mem::zeroed();
+            if webp::WebPAnimEncoderAssemble(self.ptr, &mut data) == 0 {
+                return Err(format!("WebP 인코딩 실패: {}", self.error()));
+            }
+            let bytes = std::slice::from_raw_parts(data.bytes, data.size).to_vec();
+            webp::WebPDataClear(&mut data);
+            Ok(bytes)
+        }
+    }
+
+    fn error(&self) -> String {
+        unsafe {
+            let message = webp::WebPAnimEncoderGetError(self.ptr);
+            if message.is_null() {
+                return String::new();
+            }
+            std::ffi::CStr::from_ptr(message)
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+}
+
+impl Drop for WebpAnimEncoder {
+    fn drop(&mut self) {
+        unsafe { webp::WebPAnimEncoderDelete(self.ptr) };
+    }
+}
+
+/// Add the ICC profile and XMP packet kept by the metadata policy.
+fn add_webp_metadata(data: Vec<u8>, source: &SourceInfo) -> Result<Vec<u8>, String> {
+    let chunks = [
+        (c"ICCP", source.icc_profile()),
+        (c"XMP ", source.xmp_packet()),
+    ];
+    if chunks.iter().all(|(_, chunk)| chunk.is_none()) {
+        return Ok(data);
+    }
+
+    unsafe {
+        let image = webp::WebPData {
+            bytes: data.as_ptr(),
+            size: data.len(),
+        };
+        let mux = webp::WebPMuxCreateInternal(&image, 0, webp::WebPGetMuxABIVersion());
+        if mux.is_null() {
+            return Err("WebP 메타데이터 추가 실패".to_string());
+        }
+
+        let mut result = webp::WebPMuxError::WEBP_MUX_OK;
+        for (fourcc, chunk) in chunks.iter() {
+            let Some(chunk) = chunk else {
+                continue;
+            };
+            let chunk = webp::WebPData {
+                bytes: chunk.as_ptr(),
+                size: chunk.len(),
+            };
+            result = webp::WebPMuxSetChunk(mux, fourcc.as_ptr(), &chunk, 1);
+            if result != webp::WebPMuxError::WEBP_MUX_OK {
+                break;
+            }
+        }
+        let mut assembled: webp::WebPData = std::mem::zeroed();
+        if result == webp::WebPMuxError::WEBP_MUX_OK {
+            result = webp::WebPMuxAssemble(mux, &mut assembled);
+        }
+        webp::WebPMuxDelete(mux);
+        if result != webp::WebPMuxError::WEBP_MUX_OK {
+            return Err("WebP 메타데이터 추가 실패".to_string());
+        }
+
+        let bytes = std::slice::from_raw_parts(assembled.bytes, assembled.size).to_vec();
+        webp::WebPDataClear(&mut assembled);
+        Ok(bytes)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Conversion: APNG
+// ---------------------------------------------------------------------------
+
+/// Write the delta frames as APNG. PNG has one palette for every frame, so
+/// frames are remapped onto the global palette; truecolor when it can't be built.
+fn encode_apng(
+    input_path: &Path,
+    output_path: &Path,
+    options: &CompressOptions,
+    quality: u32,
+    params: &QualityParams,
+    source: &SourceInfo,
+    progress: Progress,
+) -> Result<(), String> {
+    // The frame count goes in the header, so the palette pass also counts them
+    let (global, frame_count) = build_global_palette(input_path, quality, params, &options.gif)?;
+
+    let mut stream = FrameStream::open(input_path, params, &options.gif)?;
+    let plays = total_plays(output_repeat(&options.gif, stream.decoder.repeat));
+
+    let file = std::fs::File::create(output_path)
+        .map_err(|e| format!("APNG 출력 파일 생성 실패: {}", e))?;
+    let mut info =
+        ::png::Info::with_size(stream.decoder.width as u32, stream.decoder.height as u32);
+    info.icc_profile = source.icc_profile().map(std::borrow::Cow::Owned);
+    let mut encoder = ::png::Encoder::with_info(std::io::BufWriter::new(file), info)
+        .map_err(|e| format!("APNG 인코더 생성 실패: {}", e))?;
+    encoder.set_depth(::png::BitDepth::Eight);
+    match &global {
+        Some(global) => {
+            encoder.set_color(::png::ColorType::Indexed);
+            encoder.set_palette(palette_bytes(&global.palette));
+            let mut trns = vec![255u8; global.transparent as usize + 1];
+            trns[global.transparent as usize] = 0;
+            encoder.set_trns(trns);
+        }
+        None => encoder.set_color(::png::ColorType::Rgba),
+    }
+    encoder.set_compression(::png::Compression::High);
+    encoder
+        .set_animated(frame_count.max(1) as u32, plays)
+        .map_err(|e| format!("APNG 인코더 생성 실패: {}", e))?;
+    if let Some(xmp) = source.xmp_packet() {
+        let xmp = String::from_utf8_lossy(&xmp).into_owned();
+        let _ = encoder.add_itxt_chunk("XML:com.adobe.xmp".to_string(), xmp);
+    }
+    for comment in source.comments() {
+        let _ = encoder.add_itxt_chunk("Comment".to_string(), comment);
+    }
+
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("APNG 헤더 쓰기 실패: {}", e))?;
+    let total = source.frames.max(1) as f32;
+    while let Some(df) = stream.next_delta()? {
+        let (fw, fh) = (df.width as usize, df.height as usize);
+        let data = match &global {
+            Some(global) => {
+                let rgba = if params.lossy_tolerance > 0 {
+                    lossy_pixel_align(&df.rgba, fw, fh, params.lossy_tolerance)
+                } else {
+                    df.rgba
+                };
+                remap_to_global(&rgba, fw, fh, global, params.dithering).0
+            }
+            None => df.rgba,
+        };
+        let dispose = match df.dispose {
+            gif::DisposalMethod::Background => ::png::DisposeOp::Background,
+            gif::DisposalMethod::Previous => ::png::DisposeOp::Previous,
+            _ => ::png::DisposeOp::None,
+        };
+
+        // Transparent pixels leave the canvas as it was, like in the GIF
+        writer
+            .reset_frame_position()
+            .and_then(|_| writer.set_frame_dimension(fw as u32, fh as u32))
+            .and_then(|_| writer.set_frame_position(df.left as u32, df.top as u32))
+            .and_then(|_| writer.set_frame_delay(effective_delay(df.delay), 100))
+            .and_then(|_| writer.set_dispose_op(dispose))
+            .and_then(|_| writer.set_blend_op(::png::BlendOp::Over))
+            .and_then(|_| writer.write_image_data(&data))
+            .map_err(|e| format!("APNG 프레임 쓰기 실패: {}", e))?;
+        progress(Stage::Encode, stream.decoder.frames_read as f32 / total);
+    }
+
+    writer
+        .finish()
+        .map_err(|e| format!("APNG 쓰기 실패: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift, so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u8 {
+            (self.next() % n) as u8
+        }
+    }
+
+    /// Index stream mixing noise, long runs (the KwKwK case) and repeated
+    /// patterns.
+    fn index_stream(rng: &mut Rng, colors: u64, len: usize) -> Vec<u8> {
+        let mut indices = Vec::with_capacity(len);
+        while indices.len() < len {
+            match rng.below(3) {
+                0 => indices.extend((0..rng.below(64)).map(|_| rng.below(colors))),
+                1 => {
+                    let run = rng.below(colors);
+                    indices.extend(std::iter::repeat_n(run, rng.below(200) as usize));
+                }
+                _ => {
+                    let pattern: Vec<u8> =
+                        (0..1 + rng.below(8)).map(|_| rng.below(colors)).collect();
+                    for _ in 0..rng.below(30) {
+                        indices.extend(&pattern);
+                    }
+                }
+            }
+        }
+        indices.truncate(len);
+        indices
+    }
+
+    fn decode(encoded: &[u8]) -> Vec<u8> {
+        weezl::decode::Decoder::new(weezl::BitOrder::Lsb, encoded[0])
+            .decode(&encoded[1..])
+            .expect("LZW stream decodes")
+    }
+
+    /// A gray ramp: every index is a distinct color, and neighbors are close
+    /// enough to substitute.
+    fn gray_palette() -> Vec<u8> {
+        (0..=255u8).flat_map(|i| [i; 3]).collect()
+    }
+
+    #[test]
+    fn lossy_lzw_exact_at_zero_tolerance() {
+        let palette = gray_palette();
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for colors in [2, 4, 16, 200, 256] {
+            // Long enough to fill the code table several times
+            for len in [0, 1, 2, 100, 5_000, 60_000] {
+                let indices = index_stream(&mut rng, colors, len);
+                let encoded = lzw_encode_lossy(&indices, &palette, Some(0), 0);
+                assert_eq!(decode(&encoded), indices, "colors={colors} len={len}");
+            }
+        }
+
+        // Every prefix, so the stream ends at each code width boundary
+        for colors in [4, 16] {
+            let indices = index_stream(&mut rng, colors, 3_000);
+            for len in 0..=indices.len() {
+                let encoded = lzw_encode_lossy(&indices[..len], &palette, Some(0), 0);
+                assert_eq!(
+                    decode(&encoded),
+                    &indices[..len],
+                    "colors={colors} len={len}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lossy_lzw_stays_within_tolerance() {
+        let palette = gray_palette();
+        let transparent = 0;
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for colors in [2, 4, 16, 200, 256] {
+            for len in [1, 100, 5_000, 60_000] {
+                let indices = index_stream(&mut rng, colors, len);
+                for tolerance in [4, 20, 255] {
+                    let encoded =
+                        lzw_encode_lossy(&indices, &palette, Some(transparent), tolerance);
+                    let decoded = decode(&encoded);
+                    assert_eq!(decoded.len(), indices.len());
+                    for (&got, &want) in decoded.iter().zip(&indices) {
+                        assert_eq!(got == transparent, want == transparent);
+                        // Gray, so each channel differs by the same amount
+                        let distance = 3 * (got as u32).abs_diff(want as u32).pow(2);
+                        assert!(distance <= tolerance as u32 * tolerance as u32);
+                    }
+                }
+            }
+        }
+    }
+
+    const SPRITE: usize = 8;
+
+    /// `background` alone, then an 8x8 two-tone sprite moving right over
+    /// it. The background is opaque or fully transparent.
+    fn sprite_frames(
+        w: usize,
+        h: usize,
+        background: impl Fn(usize, usize) -> [u8; 4],
+    ) -> Vec<Vec<u8>> {
+        (0..8)
+            .map(|i| {
+                let mut canvas: Vec<u8> =
+                    (0..w * h).flat_map(|p| background(p % w, p / w)).collect();
+                if i == 0 {
+                    return canvas;
+                }
+                let (left, top) = (2 + i * 6, 4 + i % 3);
+                for y in top..top + SPRITE {
+                    for x in left..left + SPRITE {
+                        let color = if (x + y) % 2 == 0 {
+                            [220, 40, 40, 255]
+                        } else {
+                            [250, 220, 0, 255]
+                        };
+                        canvas[(y * w + x) * 4..][..4].copy_from_slice(&color);
+                    }
+                }
+                canvas
+            })
+            .collect()
+    }
+
+    /// Full-canvas frames, each cleared before the next so transparent
+    /// areas stay transparent.
+    fn write_source(path: &Path, w: usize, h: usize, frames: &[Vec<u8>]) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut encoder = gif::Encoder::new(file, w as u16, h as u16, &[]).unwrap();
+        for canvas in frames {
+            let mut rgba = canvas.clone();
+            let mut frame = gif::Frame::from_rgba(w as u16, h as u16, &mut rgba);
+            frame.delay = 10;
+            frame.dispose = gif::DisposalMethod::Background;
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+
+    fn composited_frames(path: &Path) -> Vec<Vec<u8>> {
+        let mut decoder = FrameDecoder::open(path).unwrap();
+        std::iter::from_fn(|| decoder.next_frame().unwrap().map(|f| f.canvas)).collect()
+    }
+
+    fn disposals(path: &Path) -> Vec<gif::DisposalMethod> {
+        let file = std::fs::File::open(path).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(file).unwrap();
+        std::iter::from_fn(|| decoder.read_next_frame().unwrap().map(|f| f.dispose)).collect()
+    }
+
+    #[test]
+    fn chosen_disposals_rebuild_every_frame() {
+        let (w, h) = (64, 24);
+        // Blocks of few colors, so every palette is exact
+        let scene = |x: usize, y: usize| [(x / 8 * 30) as u8, 90, (y / 6 * 60) as u8, 255];
+        let transparent = |_, _| [0; 4];
+        // Exact quantization and LZW
+        let options: CompressOptions = serde_json::from_str(r#"{"quality":95}"#).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        for (name, frames) in [
+            ("scene", sprite_frames(w, h, scene)),
+            ("transparent", sprite_frames(w, h, transparent)),
+        ] {
+            let input = dir.path().join(format!("{name}.gif"));
+            let output = dir.path().join(format!("{name}-out.gif"));
+            write_source(&input, w, h, &frames);
+            compress(&input, &output, &options, &|_, _| {}).unwrap();
+
+            assert_eq!(composited_frames(&input), frames, "{name}: source");
+            let decoded = composited_frames(&output);
+            assert_eq!(decoded.len(), frames.len(), "{name}");
+            for (i, (got, want)) in decoded.iter().zip(&frames).enumerate() {
+                assert!(got == want, "{name}: frame {i} differs");
+            }
+            // Restoring the clean background is cheaper than redrawing what
+            // the sprite left behind, and with a transparent background Keep
+            // can't clear it at all
+            assert!(
+                disposals(&output)
+                    .iter()
+                    .any(|&d| d != gif::DisposalMethod::Keep),
+                "{name}: every frame kept"
+            );
+        }
+    }
 }
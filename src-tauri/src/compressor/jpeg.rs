@@ -1,34 +1,838 @@
-use image::ImageReader;
+use super::{CompressOptions, MetadataPolicy, Progress, Stage};
+use image::ImageFormat;
+use moxcms::{ColorProfile, DataColorSpace, Layout, TransformOptions};
+use mozjpeg::qtable::{self, QTable};
+use mozjpeg::{ColorSpace, ColorSpaceExt, PixelDensity, PixelDensityUnit};
+use mozjpeg_sys as ffi;
+use std::os::raw::{c_int, c_uint, c_ulong};
 use std::path::Path;
 
-pub fn compress(input_path: &Path, output_path: &Path, quality: u32) -> Result<u64, String> {
-    let img = ImageReader::open(input_path)
-        .map_err(|e| format!("JPEG 열기 실패: {}", e))?
-        .decode()
-        .map_err(|e| format!("JPEG 디코딩 실패: {}", e))?;
+/// Rows written per `write_scanlines` call, so encode progress can be reported in between.
+const SCANLINE_BAND: usize = 64;
 
-    let rgb = img.to_rgb8();
-    let (width, height) = rgb.dimensions();
+/// How the requested quality relates to the quality the source was saved at.
+/// Re-encoding above the source quality wastes CPU, often grows the file and
+/// adds a generation of artifacts.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityPolicy {
+    /// Always re-encode at the requested quality.
+    #[default]
+    Reencode,
+    /// Re-encode at the lower of the requested and the estimated source quality.
+    CapAtSource,
+    /// When the source is already at or below the requested quality, optimize
+    /// it losslessly (Huffman + progressive scans) instead of re-encoding.
+    LosslessIfLower,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChromaSubsampling {
+    /// 4:4:4 for screenshots and other synthetic images, 4:2:0 for photos.
+    #[default]
+    Auto,
+    Yuv420,
+    Yuv422,
+    /// Full chroma resolution; keeps colored text and thin lines crisp.
+    Yuv444,
+}
+
+/// Base quantization tables, scaled by the quality setting.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuantTablePreset {
+    /// mozjpeg's default (N. Robidoux / ImageMagick tables).
+    #[default]
+    Default,
+    /// IJG / JPEG Annex K, what libjpeg-based tools use.
+    AnnexK,
+    Flat,
+    MsSsim,
+    PsnrHvs,
+}
+
+/// What to do with CMYK (print) JPEGs.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CmykPolicy {
+    /// Convert to sRGB through the embedded ICC profile, for screen and web use.
+    #[default]
+    ConvertToSrgb,
+    /// Re-encode as CMYK and keep the ICC profile, for print workflows.
+    Keep,
+}
+
+/// Advanced JPEG encoder settings.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct JpegOptions {
+    pub quality_policy: QualityPolicy,
+    pub chroma_subsampling: ChromaSubsampling,
+    /// Progressive scans (smaller, loads coarse-to-fine) instead of baseline.
+    pub progressive: bool,
+    /// Trellis quantization. Turning it off is faster and gives slightly
+    /// larger files; mozjpeg's other defaults stay on.
+    pub trellis: bool,
+    pub quant_table: QuantTablePreset,
+    pub cmyk_policy: CmykPolicy,
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self {
+            quality_policy: QualityPolicy::default(),
+            chroma_subsampling: ChromaSubsampling::default(),
+            progressive: true,
+            trellis: true,
+            quant_table: QuantTablePreset::default(),
+            cmyk_policy: CmykPolicy::default(),
+        }
+    }
+}
+
+/// Color model of the source, read from its header segments.
+enum SourceColor {
+    Gray,
+    Rgb,
+    Cmyk {
+        /// Adobe (Photoshop) CMYK JPEGs store every channel inverted.
+        inverted: bool,
+        icc: Option<Vec<u8>>,
+    },
+}
+
+/// Decoded samples ready for mozjpeg, in `color_space`'s channel layout.
+struct Pixels {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    color_space: mozjpeg::ColorSpace,
+    /// False when the samples were converted to sRGB, so the source's ICC
+    /// profile no longer describes them.
+    source_profile: bool,
+}
+
+/// Source metadata carried into the output.
+struct Metadata<'a> {
+    /// `(marker, payload)` of APPn / COM segments, written verbatim.
+    segments: Vec<(u8, &'a [u8])>,
+    density: Option<PixelDensity>,
+}
+
+pub fn compress(
+    input_path: &Path,
+    output_path: &Path,
+    options: &CompressOptions,
+    progress: Progress,
+) -> Result<u64, String> {
+    let data = std::fs::read(input_path).map_err(|e| format!("JPEG 읽기 실패: {}", e))?;
+
+    let mut quality = options.quality;
+    let policy = options.jpeg.quality_policy;
+    if policy != QualityPolicy::Reencode {
+        // A lossless transcode keeps the source's color model, so it can't
+        // honor a requested CMYK to sRGB conversion
+        let converts_cmyk = options.jpeg.cmyk_policy == CmykPolicy::ConvertToSrgb
+            && matches!(source_color(&data), SourceColor::Cmyk { .. });
+        if let Some(source_quality) = estimate_quality(&data) {
+            if policy == QualityPolicy::LosslessIfLower
+                && source_quality <= quality
+                && !converts_cmyk
+            {
+                return compress_lossless_only(&data, output_path, options, progress);
+            }
+            quality = quality.min(source_quality);
+        }
+    }
+
+    progress(Stage::Decode, 0.0);
+    let pixels = decode(&data, options.jpeg.cmyk_policy)?;
+    progress(Stage::Decode, 1.0);
+
+    let metadata = Metadata {
+        segments: metadata_segments(&data, options.metadata, pixels.source_profile),
+        density: (options.metadata != MetadataPolicy::StripAll)
+            .then(|| jfif_density(&data))
+            .flatten(),
+    };
+    let encoded = encode(&pixels, quality, &options.jpeg, metadata, progress)?;
+
+    std::fs::write(output_path, &encoded).map_err(|e| format!("JPEG 저장 실패: {}", e))?;
+
+    Ok(encoded.len() as u64)
+}
+
+fn decode(data: &[u8], cmyk_policy: CmykPolicy) -> Result<Pixels, String> {
+    let (inverted, icc) = match source_color(data) {
+        SourceColor::Cmyk { inverted, icc } => (inverted, icc),
+        color => {
+            let img = image::load_from_memory_with_format(data, ImageFormat::Jpeg)
+                .map_err(|e| format!("JPEG 디코딩 실패: {}", e))?;
+            let (width, height) = (img.width(), img.height());
+            // Grayscale stays one channel instead of tripling into RGB
+            let (data, color_space) = match color {
+                SourceColor::Gray => (img.into_luma8().into_raw(), ColorSpace::JCS_GRAYSCALE),
+                _ => (img.into_rgb8().into_raw(), ColorSpace::JCS_RGB),
+            };
+            return Ok(Pixels {
+                data,
+                width,
+                height,
+                color_space,
+                source_profile: true,
+            });
+        }
+    };
+
+    let (mut cmyk, width, height) = decode_cmyk(data)?;
+    match cmyk_policy {
+        CmykPolicy::Keep => {
+            // libjpeg always writes the Adobe marker for CMYK, so the output
+            // has to follow the inverted convention too
+            if !inverted {
+                cmyk.iter_mut().for_each(|v| *v = 255 - *v);
+            }
+            Ok(Pixels {
+                data: cmyk,
+                width,
+                height,
+                color_space: ColorSpace::JCS_CMYK,
+                source_profile: true,
+            })
+        }
+        CmykPolicy::ConvertToSrgb => {
+            if inverted {
+                cmyk.iter_mut().for_each(|v| *v = 255 - *v);
+            }
+            Ok(Pixels {
+                data: cmyk_to_srgb(&cmyk, icc.as_deref()),
+                width,
+                height,
+                color_space: ColorSpace::JCS_RGB,
+                source_profile: false,
+            })
+        }
+    }
+}
+
+fn encode(
+    pixels: &Pixels,
+    quality: u32,
+    jpeg: &JpegOptions,
+    metadata: Metadata,
+    progress: Progress,
+) -> Result<Vec<u8>, String> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+        encode_pixels(pixels, quality, jpeg, &metadata, progress)
+    }))
+    .map_err(|e| format!("mozjpeg 압축 실패: {}", panic_message(e)))
+}
+
+fn compress_lossless_only(
+    data: &[u8],
+    output_path: &Path,
+    options: &CompressOptions,
+    progress: Progress,
+) -> Result<u64, String> {
+    progress(Stage::Optimize, 0.0);
+    let segments = metadata_segments(data, options.metadata, true);
+    let optimized = optimize_lossless(data, options.jpeg.progressive, &segments)?;
+    progress(Stage::Optimize, 1.0);
+
+    std::fs::write(output_path, &optimized).map_err(|e| format!("JPEG 저장 실패: {}", e))?;
+
+    Ok(optimized.len() as u64)
+}
+
+impl ChromaSubsampling {
+    /// Cb and Cr "pixel" sizes in luma pixels, as mozjpeg expects them.
+    fn pixel_sizes(self) -> ((u8, u8), (u8, u8)) {
+        match self {
+            ChromaSubsampling::Yuv444 => ((1, 1), (1, 1)),
+            ChromaSubsampling::Yuv422 => ((2, 1), (2, 1)),
+            ChromaSubsampling::Yuv420 | ChromaSubsampling::Auto => ((2, 2), (2, 2)),
+        }
+    }
+}
+
+impl QuantTablePreset {
+    fn tables(self) -> Option<(&'static QTable, &'static QTable)> {
+        match self {
+            QuantTablePreset::Default => None,
+            QuantTablePreset::AnnexK => Some((&qtable::AnnexK_Luma, &qtable::AnnexK_Chroma)),
+            QuantTablePreset::Flat => Some((&qtable::Flat, &qtable::Flat)),
+            QuantTablePreset::MsSsim => Some((&qtable::MSSSIM_Luma, &qtable::MSSSIM_Chroma)),
+            QuantTablePreset::PsnrHvs => Some((&qtable::PSNRHVS_Luma, &qtable::PSNRHVS_Chroma)),
+        }
+    }
+}
+
+/// Screenshots, UI captures and text renders are dominated by flat runs of
+/// identical pixels, which photos almost never have. Those images need full
+/// chroma resolution or colored edges bleed.
+fn looks_synthetic(rgb: &[u8], width: u32) -> bool {
+    let width = width as usize;
+    let mut pairs = 0usize;
+    let mut identical = 0usize;
+    // Every 4th row is plenty to tell the two apart
+    for row in rgb.chunks_exact(width * 3).step_by(4) {
+        for x in 1..width {
+            pairs += 1;
+            if row[x * 3 - 3..x * 3] == row[x * 3..x * 3 + 3] {
+                identical += 1;
+            }
+        }
+    }
+    pairs > 0 && identical * 2 > pairs
+}
+
+// ---------------------------------------------------------------------------
+// Grayscale / CMYK sources
+// ---------------------------------------------------------------------------
+
+fn source_color(data: &[u8]) -> SourceColor {
+    let mut components = 3;
+    let mut adobe = false;
+    let mut icc_chunks = Vec::new();
+    for (marker, payload) in header_segments(data) {
+        match marker {
+            // SOFn (C4, C8 and CC are DHT, JPG and DAC)
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                if let Some(&n) = payload.get(5) {
+                    components = n;
+                }
+            }
+            0xE2 if payload.starts_with(b"ICC_PROFILE\0") && payload.len() > 14 => {
+                icc_chunks.push((payload[12], &payload[14..]));
+            }
+            0xEE if payload.starts_with(b"Adobe") => adobe = true,
+            _ => {}
+        }
+    }
+
+    match components {
+        1 => SourceColor::Gray,
+        4 => {
+            // Large profiles are split over several APP2 segments, numbered from 1
+            icc_chunks.sort_by_key(|&(seq, _)| seq);
+            let icc: Vec<u8> = icc_chunks
+                .into_iter()
+                .flat_map(|(_, c)| c.iter().copied())
+                .collect();
+            SourceColor::Cmyk {
+                inverted: adobe,
+                icc: (!icc.is_empty()).then_some(icc),
+            }
+        }
+        _ => SourceColor::Rgb,
+    }
+}
+
+/// Raw CMYK samples as stored in the file (YCCK is converted to CMYK by libjpeg).
+fn decode_cmyk(data: &[u8]) -> Result<(Vec<u8>, u32, u32), String> {
+    std::panic::catch_unwind(|| -> std::io::Result<_> {
+        let mut started =
+            mozjpeg::Decompress::new_mem(data)?.to_colorspace(ColorSpace::JCS_CMYK)?;
+        let (width, height) = (started.width() as u32, started.height() as u32);
+        let cmyk = started.read_scanlines::<u8>()?;
+        started.finish()?;
+        Ok((cmyk, width, height))
+    })
+    .map_err(|_| "CMYK JPEG 디코딩 실패".to_string())?
+    .map_err(|e| format!("CMYK JPEG 디코딩 실패: {}", e))
+}
+
+/// Convert CMYK ink amounts (0 = no ink) to sRGB, through the source's ICC
+/// profile when it has a usable one.
+fn cmyk_to_srgb(cmyk: &[u8], icc: Option<&[u8]>) -> Vec<u8> {
+    let mut rgb = vec![0u8; cmyk.len() / 4 * 3];
+
+    let transform = icc
+        .and_then(|icc| ColorProfile::new_from_slice(icc).ok())
+        .filter(|profile| profile.color_space == DataColorSpace::Cmyk)
+        .and_then(|profile| {
+            let srgb = ColorProfile::new_srgb();
+            profile
+                .create_transform_8bit(
+                    Layout::Rgba,
+                    &srgb,
+                    Layout::Rgb,
+                    TransformOptions::default(),
+                )
+                .ok()
+        });
+    if let Some(transform) = transform {
+        if transform.transform(cmyk, &mut rgb).is_ok() {
+            return rgb;
+        }
+    }
+
+    // No profile: the naive device conversion, still far better than nothing
+    for (ink, out) in cmyk.chunks_exact(4).zip(rgb.chunks_exact_mut(3)) {
+        let k = 255 - ink[3] as u32;
+        for c in 0..3 {
+            out[c] = ((255 - ink[c] as u32) * k / 255) as u8;
+        }
+    }
+    rgb
+}
+
+// ---------------------------------------------------------------------------
+// Metadata
+// ---------------------------------------------------------------------------
+
+/// Source APPn / COM segments to carry over under `policy`. JFIF (APP0) and
+/// Adobe (APP14) are left out because libjpeg writes its own, MPF because
+/// the re-encode invalidates it.
+fn metadata_segments(data: &[u8], policy: MetadataPolicy, keep_icc: bool) -> Vec<(u8, &[u8])> {
+    header_segments(data)
+        .filter(|&(marker, payload)| {
+            let is_icc = marker == 0xE2 && payload.starts_with(b"ICC_PROFILE\0");
+            // Multi-picture index: its offsets point into the source file
+            let is_mpf = marker == 0xE2 && payload.starts_with(b"MPF\0");
+            match policy {
+                MetadataPolicy::StripAll => false,
+                MetadataPolicy::KeepColorAndDpi => is_icc && keep_icc,
+                MetadataPolicy::KeepAll if is_icc => keep_icc,
+                MetadataPolicy::KeepAll if is_mpf => false,
+                MetadataPolicy::KeepAll => matches!(marker, 0xE1..=0xED | 0xEF | 0xFE),
+            }
+        })
+        .collect()
+}
+
+/// Pixel density from the source's JFIF header.
+fn jfif_density(data: &[u8]) -> Option<PixelDensity> {
+    let (_, jfif) = header_segments(data)
+        .find(|&(marker, payload)| marker == 0xE0 && payload.starts_with(b"JFIF\0"))?;
+    let unit = match jfif.get(7)? {
+        0 => PixelDensityUnit::PixelAspectRatio,
+        1 => PixelDensityUnit::Inches,
+        2 => PixelDensityUnit::Centimeters,
+        _ => return None,
+    };
+    Some(PixelDensity {
+        unit,
+        x: u16::from_be_bytes([*jfif.get(8)?, *jfif.get(9)?]),
+        y: u16::from_be_bytes([*jfif.get(10)?, *jfif.get(11)?]),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Source quality estimation from DQT tables
+// ---------------------------------------------------------------------------
+
+/// Zigzag scan position → natural (row-major) coefficient index.
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// IJG / JPEG Annex K base tables (natural order), scaled by the libjpeg quality formula.
+const STD_LUMA_QTABLE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+const STD_CHROMA_QTABLE: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+/// Estimate the libjpeg-style quality (1–100) a JPEG was saved at, by comparing
+/// its quantization tables against the standard tables. Encoders with custom
+/// tables get a rough equivalent rather than their nominal setting.
+pub fn estimate_quality(data: &[u8]) -> Option<u32> {
+    let tables = read_quant_tables(data);
+    let mut sum = 0u64;
+    let mut std_sum = 0u64;
+    for (id, table) in &tables {
+        let std_table = match id {
+            0 => &STD_LUMA_QTABLE,
+            1 => &STD_CHROMA_QTABLE,
+            _ => continue,
+        };
+        for (k, &q) in table.iter().enumerate() {
+            sum += q as u64;
+            std_sum += std_table[ZIGZAG[k]] as u64;
+        }
+    }
+    if std_sum == 0 {
+        return None;
+    }
+
+    // Invert libjpeg's scaling: scale = 5000 / q below 50, 200 - 2q from 50 up
+    let scale = sum as f64 * 100.0 / std_sum as f64;
+    let quality = if scale <= 100.0 {
+        (200.0 - scale) / 2.0
+    } else {
+        5000.0 / scale
+    };
+    Some(quality.round().clamp(1.0, 100.0) as u32)
+}
+
+pub fn estimate_quality_file(path: &Path) -> Option<u32> {
+    estimate_quality(&std::fs::read(path).ok()?)
+}
+
+/// Collect the DQT tables (in zigzag order) from the header segments.
+fn read_quant_tables(data: &[u8]) -> Vec<(u8, [u16; 64])> {
+    let mut tables = Vec::new();
+    for (_, mut seg) in header_segments(data).filter(|&(marker, _)| marker == 0xDB) {
+        while let Some((&pq_tq, rest)) = seg.split_first() {
+            let sixteen_bit = pq_tq >> 4 != 0;
+            let size = if sixteen_bit { 128 } else { 64 };
+            if rest.len() < size {
+                break;
+            }
+            let mut table = [0u16; 64];
+            for (k, q) in table.iter_mut().enumerate() {
+                *q = if sixteen_bit {
+                    u16::from_be_bytes([rest[k * 2], rest[k * 2 + 1]])
+                } else {
+                    rest[k] as u16
+                };
+            }
+            tables.push((pq_tq & 0x0F, table));
+            seg = &rest[size..];
+        }
+    }
+    tables
+}
+
+/// `(marker, payload)` of each header segment before the first scan.
+fn header_segments(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut pos = if data.starts_with(&[0xFF, 0xD8]) {
+        2
+    } else {
+        data.len()
+    };
+    std::iter::from_fn(move || {
+        while pos + 4 <= data.len() && data[pos] == 0xFF {
+            let marker = data[pos + 1];
+            if marker == 0xFF {
+                // Fill byte
+                pos += 1;
+                continue;
+            }
+            // SOS or EOI: no more header segments
+            if marker == 0xDA || marker == 0xD9 {
+                return None;
+            }
+            let len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            let end = (pos + 2 + len).min(data.len());
+            let payload = data.get(pos + 4..end).unwrap_or_default();
+            pos = end;
+            return Some((marker, payload));
+        }
+        None
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Re-encode via libjpeg's compress API
+// ---------------------------------------------------------------------------
+
+/// mozjpeg's trellis quantization passes, all on by default.
+const TRELLIS_PARAMS: [ffi::J_BOOLEAN_PARAM; 5] = [
+    ffi::J_BOOLEAN_PARAM::JBOOLEAN_TRELLIS_QUANT,
+    ffi::J_BOOLEAN_PARAM::JBOOLEAN_TRELLIS_QUANT_DC,
+    ffi::J_BOOLEAN_PARAM::JBOOLEAN_TRELLIS_EOB_OPT,
+    ffi::J_BOOLEAN_PARAM::JBOOLEAN_TRELLIS_Q_OPT,
+    ffi::J_BOOLEAN_PARAM::JBOOLEAN_USE_SCANS_IN_TRELLIS,
+];
+
+/// libjpeg state for one encode, released on drop like `Transcoder`.
+struct Encoder {
+    cinfo: ffi::jpeg_compress_struct,
+    err: ffi::jpeg_error_mgr,
+    out_buf: *mut u8,
+    out_size: c_ulong,
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::jpeg_destroy_compress(&mut self.cinfo);
+            if !self.out_buf.is_null() {
+                libc::free(self.out_buf.cast());
+            }
+        }
+    }
+}
+
+unsafe fn encode_pixels(
+    pixels: &Pixels,
+    quality: u32,
+    jpeg: &JpegOptions,
+    metadata: &Metadata,
+    progress: Progress,
+) -> Vec<u8> {
+    let mut e: Box<Encoder> = Box::new(std::mem::zeroed());
+    e.cinfo.common.err = ffi::jpeg_std_error(&mut e.err);
+    e.err.error_exit = Some(unwind_error_exit);
+    ffi::jpeg_create_compress(&mut e.cinfo);
+
+    let Encoder {
+        cinfo,
+        out_buf,
+        out_size,
+        ..
+    } = &mut *e;
+    let components = pixels.color_space.num_components();
+    cinfo.in_color_space = pixels.color_space;
+    cinfo.input_components = components as c_int;
+    cinfo.image_width = pixels.width;
+    cinfo.image_height = pixels.height;
+    // mozjpeg's defaults: trellis, deringing and progressive scan optimization
+    ffi::jpeg_set_defaults(cinfo);
+    if !jpeg.trellis {
+        for param in TRELLIS_PARAMS {
+            ffi::jpeg_c_set_bool_param(cinfo, param, 0);
+        }
+    }
+    if let Some(density) = &metadata.density {
+        cinfo.density_unit = density.unit as u8;
+        cinfo.X_density = density.x;
+        cinfo.Y_density = density.y;
+    }
+    ffi::jpeg_set_quality(cinfo, quality as c_int, 0);
+    if let Some((luma, chroma)) = jpeg.quant_table.tables() {
+        let q = quality.clamp(1, 100) as f32;
+        let (luma, chroma) = (luma.scaled(q, q), chroma.scaled(q, q));
+        ffi::jpeg_add_quant_table(cinfo, 0, luma.as_ptr(), 100, 1);
+        ffi::jpeg_add_quant_table(cinfo, 1, chroma.as_ptr(), 100, 1);
+    }
+
+    // Grayscale has no chroma, and CMYK channels are all kept at full resolution
+    if pixels.color_space == ColorSpace::JCS_RGB {
+        let subsampling = match jpeg.chroma_subsampling {
+            ChromaSubsampling::Auto if looks_synthetic(&pixels.data, pixels.width) => {
+                ChromaSubsampling::Yuv444
+            }
+            ChromaSubsampling::Auto => ChromaSubsampling::Yuv420,
+            fixed => fixed,
+        };
+        let (cb, cr) = subsampling.pixel_sizes();
+        let max = (cb.0.max(cr.0), cb.1.max(cr.1));
+        let comp_info = std::slice::from_raw_parts_mut(cinfo.comp_info, 3);
+        for (c, (h, v)) in comp_info.iter_mut().zip([(1, 1), cb, cr]) {
+            c.h_samp_factor = (max.0 / h) as c_int;
+            c.v_samp_factor = (max.1 / v) as c_int;
+        }
+    }
+
+    if jpeg.progressive {
+        ffi::jpeg_simple_progression(cinfo);
+        ffi::jpeg_c_set_bool_param(cinfo, ffi::J_BOOLEAN_PARAM::JBOOLEAN_OPTIMIZE_SCANS, 1);
+    } else {
+        // Without a scan script libjpeg writes a single baseline scan
+        ffi::jpeg_c_set_bool_param(cinfo, ffi::J_BOOLEAN_PARAM::JBOOLEAN_OPTIMIZE_SCANS, 0);
+        cinfo.scan_info = std::ptr::null();
+    }
+
+    ffi::jpeg_mem_dest(cinfo, out_buf, out_size);
+    ffi::jpeg_start_compress(cinfo, 1);
+    for &(marker, payload) in &metadata.segments {
+        let len = payload.len() as c_uint;
+        ffi::jpeg_write_marker(cinfo, marker as c_int, payload.as_ptr(), len);
+    }
+
+    let row_bytes = pixels.width as usize * components;
+    let rows: Vec<ffi::JSAMPROW> = pixels
+        .data
+        .chunks_exact(row_bytes)
+        .map(<[u8]>::as_ptr)
+        .collect();
+    let bands = rows.chunks(SCANLINE_BAND);
+    let band_count = bands.len();
+    for (i, band) in bands.enumerate() {
+        // The memory destination never suspends, so every row is taken
+        ffi::jpeg_write_scanlines(cinfo, band.as_ptr(), band.len() as c_uint);
+        progress(Stage::Encode, (i + 1) as f32 / band_count as f32);
+    }
+
+    // Progressive scans are entropy-coded and optimized in finish
+    progress(Stage::Optimize, 0.0);
+    ffi::jpeg_finish_compress(cinfo);
+    progress(Stage::Optimize, 1.0);
+
+    std::slice::from_raw_parts(e.out_buf, e.out_size as usize).to_vec()
+}
+
+// ---------------------------------------------------------------------------
+// Lossless transcode (jpegtran-style) via libjpeg's coefficient API
+// ---------------------------------------------------------------------------
+
+/// Re-write the source's DCT coefficients with optimized Huffman tables and
+/// progressive scans. Pixels are untouched, so there is no generational loss.
+fn optimize_lossless(
+    data: &[u8],
+    progressive: bool,
+    segments: &[(u8, &[u8])],
+) -> Result<Vec<u8>, String> {
+    std::panic::catch_unwind(|| unsafe { transcode_coefficients(data, progressive, segments) })
+        .map_err(|e| format!("JPEG 무손실 최적화 실패: {}", panic_message(e)))
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .unwrap_or_else(|| "알 수 없는 오류".to_string())
+}
+
+/// libjpeg state for one transcode. Boxed because the structs point at their
+/// own error managers; dropping it (also while unwinding) releases everything.
+struct Transcoder {
+    src: ffi::jpeg_decompress_struct,
+    dst: ffi::jpeg_compress_struct,
+    src_err: ffi::jpeg_error_mgr,
+    dst_err: ffi::jpeg_error_mgr,
+    out_buf: *mut u8,
+    out_size: c_ulong,
+}
+
+impl Drop for Transcoder {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::jpeg_destroy_compress(&mut self.dst);
+            ffi::jpeg_destroy_decompress(&mut self.src);
+            if !self.out_buf.is_null() {
+                libc::free(self.out_buf.cast());
+            }
+        }
+    }
+}
+
+/// Corrupt-data warnings are not actionable here; fatal errors still unwind.
+extern "C-unwind" fn silence_message(_cinfo: &mut ffi::jpeg_common_struct, _level: c_int) {}
+
+/// libjpeg can't return from a fatal error, so unwind back to `catch_unwind`.
+extern "C-unwind" fn unwind_error_exit(cinfo: &mut ffi::jpeg_common_struct) {
+    let code = unsafe { cinfo.err.as_ref().map(|e| e.msg_code).unwrap_or(0) };
+    std::panic::resume_unwind(Box::new(format!("libjpeg 오류 코드 {}", code)));
+}
+
+unsafe fn transcode_coefficients(
+    data: &[u8],
+    progressive: bool,
+    segments: &[(u8, &[u8])],
+) -> Vec<u8> {
+    let mut t: Box<Transcoder> = Box::new(std::mem::zeroed());
+
+    t.src.common.err = ffi::jpeg_std_error(&mut t.src_err);
+    t.src_err.error_exit = Some(unwind_error_exit);
+    t.src_err.emit_message = Some(silence_message);
+    ffi::jpeg_create_decompress(&mut t.src);
+    ffi::jpeg_mem_src(&mut t.src, data.as_ptr(), data.len() as c_ulong);
+    ffi::jpeg_read_header(&mut t.src, 1);
+    let coefficients = ffi::jpeg_read_coefficients(&mut t.src);
+
+    t.dst.common.err = ffi::jpeg_std_error(&mut t.dst_err);
+    t.dst_err.error_exit = Some(unwind_error_exit);
+    t.dst_err.emit_message = Some(silence_message);
+    ffi::jpeg_create_compress(&mut t.dst);
+    ffi::jpeg_copy_critical_parameters(&t.src, &mut t.dst);
+    t.dst.optimize_coding = 1;
+    if progressive {
+        ffi::jpeg_simple_progression(&mut t.dst);
+    } else {
+        t.dst.scan_info = std::ptr::null();
+    }
+
+    let Transcoder {
+        dst,
+        out_buf,
+        out_size,
+        ..
+    } = &mut *t;
+    ffi::jpeg_mem_dest(dst, out_buf, out_size);
+    ffi::jpeg_write_coefficients(&mut t.dst, coefficients);
+    // Markers go right after the JFIF header libjpeg has just written
+    for &(marker, payload) in segments {
+        let len = payload.len() as c_uint;
+        ffi::jpeg_write_marker(&mut t.dst, marker as c_int, payload.as_ptr(), len);
+    }
+    ffi::jpeg_finish_compress(&mut t.dst);
+    ffi::jpeg_finish_decompress(&mut t.src);
+
+    std::slice::from_raw_parts(t.out_buf, t.out_size as usize).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
-    comp.set_size(width as usize, height as usize);
-    comp.set_quality(quality as f32);
-    comp.set_progressive_mode();
-    comp.set_optimize_scans(true);
+    /// A smooth gradient, so every scan has coefficients to code.
+    fn gradient(width: u32, height: u32, color_space: ColorSpace) -> Pixels {
+        let channels = color_space.num_components();
+        let mut data = Vec::with_capacity(width as usize * height as usize * channels);
+        for y in 0..height {
+            for x in 0..width {
+                let v = [(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8];
+                data.extend_from_slice(&v[..channels]);
+            }
+        }
+        Pixels {
+            data,
+            width,
+            height,
+            color_space,
+            source_profile: true,
+        }
+    }
 
-    let mut started = comp
-        .start_compress(Vec::new())
-        .map_err(|e| format!("mozjpeg 압축 시작 실패: {}", e))?;
+    #[test]
+    fn encodes_without_trellis() {
+        for color_space in [ColorSpace::JCS_RGB, ColorSpace::JCS_GRAYSCALE] {
+            for progressive in [true, false] {
+                let jpeg = JpegOptions {
+                    trellis: false,
+                    progressive,
+                    ..Default::default()
+                };
+                let pixels = gradient(64, 48, color_space);
+                let metadata = Metadata {
+                    segments: Vec::new(),
+                    density: None,
+                };
+                let encoded = encode(&pixels, 80, &jpeg, metadata, &|_, _| {}).unwrap();
 
-    started
-        .write_scanlines(rgb.as_raw())
-        .map_err(|e| format!("mozjpeg 스캔라인 쓰기 실패: {}", e))?;
+                let decoded =
+                    image::load_from_memory_with_format(&encoded, ImageFormat::Jpeg).unwrap();
+                assert_eq!((decoded.width(), decoded.height()), (64, 48));
+                assert_eq!(
+                    decoded.color().channel_count() as usize,
+                    color_space.num_components()
+                );
+            }
+        }
+    }
 
-    let data = started
-        .finish()
-        .map_err(|e| format!("mozjpeg 압축 완료 실패: {}", e))?;
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let len = (payload.len() + 2) as u16;
+        [&[0xFF, marker][..], &len.to_be_bytes(), payload].concat()
+    }
 
-    std::fs::write(output_path, &data).map_err(|e| format!("JPEG 저장 실패: {}", e))?;
+    #[test]
+    fn keep_all_leaves_out_mpf() {
+        let data = [
+            vec![0xFF, 0xD8],
+            segment(0xE1, b"Exif\0\0II*\0"),
+            segment(0xE2, b"ICC_PROFILE\0\x01\x01"),
+            segment(0xE2, b"MPF\0II*\0"),
+            segment(0xFE, b"comment"),
+            vec![0xFF, 0xDA],
+        ]
+        .concat();
 
-    Ok(data.len() as u64)
+        let segments = metadata_segments(&data, MetadataPolicy::KeepAll, true);
+        let prefixes: Vec<&[u8]> = segments.iter().map(|(_, p)| &p[..4]).collect();
+        assert_eq!(prefixes, [&b"Exif"[..], b"ICC_", b"comm"]);
+    }
 }
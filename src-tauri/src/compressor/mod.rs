@@ -2,11 +2,77 @@ pub mod gif;
 pub mod jpeg;
 pub mod png;
 
-use crate::utils::ImageFormat;
-use std::path::Path;
+use crate::utils::{self, ImageFormat};
+use std::path::{Path, PathBuf};
+
+/// Settings that determine the compressed output. Everything here is part of
+/// the result cache key, so batch-level settings (output folder, workers) stay
+/// in `CompressionSettings`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CompressOptions {
+    pub quality: u32,
+    /// Don't write an output when compression doesn't beat the original.
+    #[serde(default)]
+    pub skip_unoptimized: bool,
+    /// Keep the original unless the output is at least this many percent smaller.
+    #[serde(default)]
+    pub min_savings_percent: f32,
+    /// Keep the original unless the output saves at least this many bytes.
+    #[serde(default)]
+    pub min_savings_bytes: u64,
+    #[serde(default)]
+    pub jpeg: jpeg::JpegOptions,
+    #[serde(default)]
+    pub png: png::PngOptions,
+    #[serde(default)]
+    pub gif: gif::GifOptions,
+    #[serde(default)]
+    pub metadata: MetadataPolicy,
+}
+
+/// Which metadata is carried from the source into the output, for every format.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataPolicy {
+    /// Drop everything that isn't needed to decode the image.
+    StripAll,
+    /// Keep color profiles and gamma, which change how pixels display, and
+    /// pixel density (DPI). Drop text, EXIF and the like.
+    #[default]
+    KeepColorAndDpi,
+    /// Keep all metadata that still applies to the re-encoded pixels.
+    KeepAll,
+}
+
+/// Pipeline stage reported through per-image progress events.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Decode,
+    Quantize,
+    Encode,
+    Optimize,
+}
+
+/// Per-image progress callback: the current stage and a 0.0–1.0 fraction within it.
+/// Must be `Sync` because the GIF pipeline reports from rayon workers.
+pub type Progress<'a> = &'a (dyn Fn(Stage, f32) + Sync);
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionStatus {
+    /// The re-encoded output was smaller and was written.
+    Compressed,
+    /// Nothing to gain; the original was copied, or not written at all with `skip_unoptimized`.
+    KeptOriginal,
+    /// Reused an earlier run's output without compressing again.
+    Skipped,
+    Failed,
+}
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CompressionResult {
+    pub status: CompressionStatus,
     pub success: bool,
     pub original_size: u64,
     pub compressed_size: u64,
@@ -18,49 +84,44 @@ pub fn compress(
     input_path: &Path,
     output_path: &Path,
     format: ImageFormat,
-    quality: u32,
+    options: &CompressOptions,
+    progress: Progress,
 ) -> CompressionResult {
     let original_size = std::fs::metadata(input_path)
         .map(|m| m.len())
         .unwrap_or(0);
 
-    let result = match format {
-        ImageFormat::Jpeg => jpeg::compress(input_path, output_path, quality),
-        ImageFormat::Png => png::compress(input_path, output_path, quality),
-        ImageFormat::Gif => gif::compress(input_path, output_path, quality),
-    };
+    // Encode beside the output and move it into place only once finished, so
+    // an interrupted run never leaves a truncated file at the output path
+    let dir = output_path.parent().unwrap_or(Path::new("."));
+    let result = tempfile::NamedTempFile::new_in(dir)
+        .map_err(|e| format!("임시 파일 생성 실패: {}", e))
+        .and_then(|tmp| {
+            let compressed_size = match format {
+                ImageFormat::Jpeg => jpeg::compress(input_path, tmp.path(), options, progress),
+                ImageFormat::Png => png::compress(input_path, tmp.path(), options, progress),
+                ImageFormat::Gif => gif::compress(input_path, tmp.path(), options, progress),
+            }?;
+            if !worth_keeping(original_size, compressed_size, options) {
+                return Ok(None);
+            }
+            tmp.persist(output_path)
+                .map_err(|e| format!("출력 저장 실패: {}", e))?;
+            Ok(Some(compressed_size))
+        });
 
     match result {
-        Ok(compressed_size) => {
-            // If compressed is larger than original, copy original instead
-            if compressed_size >= original_size {
-                if let Err(e) = std::fs::copy(input_path, output_path) {
-                    return CompressionResult {
-                        success: false,
-                        original_size,
-                        compressed_size: 0,
-                        output_path: output_path.to_string_lossy().to_string(),
-                        error: Some(format!("원본 복사 실패: {}", e)),
-                    };
-                }
-                CompressionResult {
-                    success: true,
-                    original_size,
-                    compressed_size: original_size,
-                    output_path: output_path.to_string_lossy().to_string(),
-                    error: None,
-                }
-            } else {
-                CompressionResult {
-                    success: true,
-                    original_size,
-                    compressed_size,
-                    output_path: output_path.to_string_lossy().to_string(),
-                    error: None,
-                }
-            }
-        }
+        Ok(Some(compressed_size)) => CompressionResult {
+            status: CompressionStatus::Compressed,
+            success: true,
+            original_size,
+            compressed_size,
+            output_path: output_path.to_string_lossy().to_string(),
+            error: None,
+        },
+        Ok(None) => keep_original(input_path, output_path, original_size, options),
         Err(e) => CompressionResult {
+            status: CompressionStatus::Failed,
             success: false,
             original_size,
             compressed_size: 0,
@@ -69,3 +130,93 @@ pub fn compress(
         },
     }
 }
+
+/// File name of the output for an input named `name`. GIFs converted to
+/// another format take that format's extension.
+pub fn output_name(name: &str, format: ImageFormat, options: &CompressOptions) -> String {
+    match (format, options.gif.target.extension()) {
+        (ImageFormat::Gif, Some(ext)) => Path::new(name)
+            .with_extension(ext)
+            .to_string_lossy()
+            .to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Where the original goes when the re-encode isn't kept: the output path,
+/// under the input's own extension if the output was converted.
+pub fn kept_original_path(input_path: &Path, output_path: &Path) -> PathBuf {
+    match input_path.extension() {
+        Some(ext) if output_path.extension() != Some(ext) => output_path.with_extension(ext),
+        _ => output_path.to_path_buf(),
+    }
+}
+
+/// Copy `from` to `to` through a temp file, so `to` is never left half-copied.
+pub fn copy_into_place(from: &Path, to: &Path) -> std::io::Result<()> {
+    let dir = to.parent().unwrap_or(Path::new("."));
+    let tmp = tempfile::NamedTempFile::new_in(dir)?;
+    std::fs::copy(from, tmp.path())?;
+    tmp.persist(to).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Whether the savings justify replacing the original with a re-encode.
+/// Both thresholds must be met; a lossy re-encode for a fraction of a percent
+/// is all cost and no benefit.
+fn worth_keeping(original_size: u64, compressed_size: u64, options: &CompressOptions) -> bool {
+    if compressed_size >= original_size {
+        return false;
+    }
+    let saved = original_size - compressed_size;
+    let saved_percent = saved as f64 / original_size as f64 * 100.0;
+    saved >= options.min_savings_bytes && saved_percent >= options.min_savings_percent as f64
+}
+
+/// The re-encoded output isn't worth keeping: replace it with a copy of the
+/// original, or drop it entirely when `skip_unoptimized` is set.
+fn keep_original(
+    input_path: &Path,
+    output_path: &Path,
+    original_size: u64,
+    options: &CompressOptions,
+) -> CompressionResult {
+    let kept_path = kept_original_path(input_path, output_path);
+    let written = if options.skip_unoptimized {
+        if !utils::same_file(output_path, input_path) {
+            let _ = std::fs::remove_file(output_path);
+        }
+        // Nothing was written; point at the original so previews still work
+        input_path
+    } else if utils::same_file(&kept_path, input_path) {
+        // Converted in place: the original already sits where it would be copied
+        if kept_path != output_path {
+            let _ = std::fs::remove_file(output_path);
+        }
+        input_path
+    } else {
+        if kept_path != output_path {
+            let _ = std::fs::remove_file(output_path);
+        }
+        if let Err(e) = copy_into_place(input_path, &kept_path) {
+            return CompressionResult {
+                status: CompressionStatus::Failed,
+                success: false,
+                original_size,
+                compressed_size: 0,
+                output_path: output_path.to_string_lossy().to_string(),
+                error: Some(format!("원본 복사 실패: {}", e)),
+            };
+        }
+        &kept_path
+    };
+
+    CompressionResult {
+        status: CompressionStatus::KeptOriginal,
+        success: true,
+        original_size,
+        compressed_size: original_size,
+        output_path: written.to_string_lossy().to_string(),
+        error: None,
+    }
+}
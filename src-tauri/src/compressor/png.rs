@@ -1,17 +1,92 @@
+use super::{CompressOptions, MetadataPolicy, Progress, Stage};
 use std::path::Path;
 
-pub fn compress(input_path: &Path, output_path: &Path, quality: u32) -> Result<u64, String> {
+/// Chunks that change how pixel values display (profiles, gamma, primaries,
+/// HDR info), plus physical pixel size.
+const COLOR_AND_DPI_CHUNKS: [[u8; 4]; 8] = [
+    *b"iCCP", *b"sRGB", *b"gAMA", *b"cHRM", *b"cICP", *b"mDCV", *b"cLLI", *b"pHYs",
+];
+
+/// APNG chunks; stripping them would turn an animation into its first frame.
+const ANIMATION_CHUNKS: [[u8; 4]; 3] = [*b"acTL", *b"fcTL", *b"fdAT"];
+
+/// Chunks tied to the source's pixel format or palette, plus signatures and
+/// offsets that any change invalidates. Never carried into a re-encode.
+const SOURCE_BOUND_CHUNKS: [[u8; 4]; 7] = [
+    *b"tRNS", *b"bKGD", *b"hIST", *b"sBIT", *b"sPLT", *b"caBX", *b"iDOT",
+];
+
+/// PNG encoder settings.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct PngOptions {
+    /// Never quantize, and keep the source bit depth and color type. For
+    /// 16-bit scientific and grayscale medical images that must stay exact.
+    pub lossless: bool,
+    /// Lowest acceptable quantization quality (0–100). When the palette can't
+    /// reach it, the image is only optimized losslessly.
+    pub min_quality: u32,
+    /// Palette size cap (2–256), e.g. 64 for icons.
+    pub max_colors: u32,
+    /// Floyd–Steinberg dithering strength, 0.0 (none) to 1.0.
+    pub dithering: f32,
+    /// Drop this many low bits per channel (0–4) before quantizing. Smaller
+    /// files at the cost of banding; useful for low-color-depth targets.
+    pub posterize: u8,
+    /// oxipng preset (0–6). 1 suits interactive use, 6 final builds.
+    pub effort: u8,
+    /// Deflate with Zopfli instead of libdeflate: a few percent smaller for
+    /// many times the CPU.
+    pub zopfli: bool,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            lossless: false,
+            min_quality: 0,
+            max_colors: 256,
+            dithering: 1.0,
+            posterize: 0,
+            effort: 2,
+            zopfli: false,
+        }
+    }
+}
+
+pub fn compress(
+    input_path: &Path,
+    output_path: &Path,
+    options: &CompressOptions,
+    progress: Progress,
+) -> Result<u64, String> {
+    let png = &options.png;
+    let data = std::fs::read(input_path).map_err(|e| format!("PNG 읽기 실패: {}", e))?;
+    // Quantizing decodes only the default image and would drop the animation.
+    // oxipng keeps the APNG chunks and recompresses every frame.
+    if png.lossless || is_animated(&data) {
+        return compress_lossless_only(&data, output_path, options, progress);
+    }
+    let quality = options.quality;
+
     // Step 1: Decode with lodepng
-    let image = lodepng::decode32_file(input_path)
-        .map_err(|e| format!("PNG 디코딩 실패: {}", e))?;
+    progress(Stage::Decode, 0.0);
+    let image = lodepng::decode32(&data).map_err(|e| format!("PNG 디코딩 실패: {}", e))?;
+    progress(Stage::Decode, 1.0);
 
     let width = image.width;
     let height = image.height;
 
     // Step 2: Lossy quantization with imagequant
+    progress(Stage::Quantize, 0.0);
     let mut liq = imagequant::new();
-    liq.set_quality(0, quality as u8)
+    let min_quality = png.min_quality.min(quality);
+    liq.set_quality(min_quality as u8, quality as u8)
         .map_err(|e| format!("imagequant 품질 설정 실패: {}", e))?;
+    liq.set_max_colors(png.max_colors.clamp(2, 256))
+        .map_err(|e| format!("imagequant 색상 수 설정 실패: {}", e))?;
+    liq.set_min_posterization(png.posterize.min(4))
+        .map_err(|e| format!("imagequant 포스터화 설정 실패: {}", e))?;
 
     let mut img = liq
         .new_image(image.buffer.as_slice(), width, height, 0.0)
@@ -20,19 +95,22 @@ pub fn compress(input_path: &Path, output_path: &Path, quality: u32) -> Result<u
     let mut res = match liq.quantize(&mut img) {
         Ok(res) => res,
         Err(_) => {
-            // If quantization fails, fall back to lossless-only optimization
-            return compress_lossless_only(input_path, output_path);
+            // If quantization fails or can't reach min_quality, fall back to
+            // lossless-only optimization
+            return compress_lossless_only(&data, output_path, options, progress);
         }
     };
 
-    res.set_dithering_level(1.0)
+    res.set_dithering_level(png.dithering.clamp(0.0, 1.0))
         .map_err(|e| format!("디더링 설정 실패: {}", e))?;
 
     let (palette, pixels) = res
         .remapped(&mut img)
         .map_err(|e| format!("imagequant 리맵 실패: {}", e))?;
+    progress(Stage::Quantize, 1.0);
 
     // Step 3: Encode quantized result with lodepng
+    progress(Stage::Encode, 0.0);
     let mut encoder = lodepng::Encoder::new();
     encoder.set_auto_convert(false);
 
@@ -74,35 +152,120 @@ pub fn compress(input_path: &Path, output_path: &Path, quality: u32) -> Result<u
     let png_data = encoder
         .encode(&pixels, width, height)
         .map_err(|e| format!("PNG 인코딩 실패: {}", e))?;
+    // lodepng writes only the image itself; bring the source's metadata along
+    let png_data = carry_chunks(&data, png_data, options.metadata);
+    progress(Stage::Encode, 1.0);
 
     // Step 4: Lossless optimization with oxipng
-    let optimized = oxipng::optimize_from_memory(
-        &png_data,
-        &oxipng::Options {
-            strip: oxipng::StripChunks::Safe,
-            ..oxipng::Options::from_preset(2)
-        },
-    )
-    .map_err(|e| format!("oxipng 최적화 실패: {}", e))?;
+    // (oxipng exposes no per-trial hook, so only the stage boundaries are reported)
+    progress(Stage::Optimize, 0.0);
+    let optimized = oxipng::optimize_from_memory(&png_data, &oxipng_options(options))
+        .map_err(|e| format!("oxipng 최적화 실패: {}", e))?;
+    progress(Stage::Optimize, 1.0);
 
     std::fs::write(output_path, &optimized).map_err(|e| format!("PNG 저장 실패: {}", e))?;
 
     Ok(optimized.len() as u64)
 }
 
-fn compress_lossless_only(input_path: &Path, output_path: &Path) -> Result<u64, String> {
-    let data = std::fs::read(input_path).map_err(|e| format!("PNG 읽기 실패: {}", e))?;
-
-    let optimized = oxipng::optimize_from_memory(
-        &data,
-        &oxipng::Options {
-            strip: oxipng::StripChunks::Safe,
-            ..oxipng::Options::from_preset(2)
-        },
-    )
-    .map_err(|e| format!("oxipng 최적화 실패: {}", e))?;
+fn compress_lossless_only(
+    data: &[u8],
+    output_path: &Path,
+    options: &CompressOptions,
+    progress: Progress,
+) -> Result<u64, String> {
+    progress(Stage::Optimize, 0.0);
+    let optimized = oxipng::optimize_from_memory(data, &oxipng_options(options))
+        .map_err(|e| format!("oxipng 최적화 실패: {}", e))?;
+    progress(Stage::Optimize, 1.0);
 
     std::fs::write(output_path, &optimized).map_err(|e| format!("PNG 저장 실패: {}", e))?;
 
     Ok(optimized.len() as u64)
 }
+
+/// oxipng runs its filter/deflate trials without a progress callback, so
+/// callers report `Stage::Optimize` only before and after the call.
+fn oxipng_options(options: &CompressOptions) -> oxipng::Options {
+    let png = &options.png;
+    let strip = match options.metadata {
+        MetadataPolicy::StripAll => oxipng::StripChunks::Keep(ANIMATION_CHUNKS.into()),
+        MetadataPolicy::KeepColorAndDpi => oxipng::StripChunks::Keep(
+            COLOR_AND_DPI_CHUNKS
+                .into_iter()
+                .chain(ANIMATION_CHUNKS)
+                .collect(),
+        ),
+        MetadataPolicy::KeepAll => oxipng::StripChunks::None,
+    };
+    let mut options = oxipng::Options {
+        strip,
+        ..oxipng::Options::from_preset(png.effort.min(6))
+    };
+    if png.zopfli {
+        options.deflater = oxipng::Deflater::Zopfli(Default::default());
+    }
+    if png.lossless {
+        // These are lossless too, but they change the pixel format, which
+        // downstream tools for 16-bit and grayscale data may depend on
+        options.bit_depth_reduction = false;
+        options.color_type_reduction = false;
+        options.palette_reduction = false;
+        options.grayscale_reduction = false;
+        options.scale_16 = false;
+    }
+    options
+}
+
+/// Copy the source's ancillary chunks allowed by `policy` into a freshly
+/// encoded PNG, right after its IHDR. Chunks are copied verbatim, CRC included.
+fn carry_chunks(source: &[u8], encoded: Vec<u8>, policy: MetadataPolicy) -> Vec<u8> {
+    let carried: Vec<u8> = chunks(source)
+        .filter(|(name, _)| keeps_chunk(policy, name))
+        .flat_map(|(_, raw)| raw.iter().copied())
+        .collect();
+    let Some((_, ihdr)) = chunks(&encoded).next() else {
+        return encoded;
+    };
+    if carried.is_empty() {
+        return encoded;
+    }
+    // Color chunks must precede PLTE and IDAT; right after IHDR is valid for
+    // every other ancillary chunk too
+    let split = 8 + ihdr.len();
+    [&encoded[..split], &carried, &encoded[split..]].concat()
+}
+
+fn keeps_chunk(policy: MetadataPolicy, name: &[u8; 4]) -> bool {
+    // Critical chunks (uppercase first letter) belong to the new encoding
+    if !name[0].is_ascii_lowercase() || SOURCE_BOUND_CHUNKS.contains(name) {
+        return false;
+    }
+    match policy {
+        MetadataPolicy::StripAll => false,
+        MetadataPolicy::KeepColorAndDpi => COLOR_AND_DPI_CHUNKS.contains(name),
+        // Unknown chunks marked unsafe to copy (uppercase last letter) depend
+        // on the image data, which the re-encode changes
+        MetadataPolicy::KeepAll => COLOR_AND_DPI_CHUNKS.contains(name) || name[3] & 0x20 != 0,
+    }
+}
+
+/// APNG: an `acTL` chunk before the first `IDAT`.
+fn is_animated(data: &[u8]) -> bool {
+    chunks(data)
+        .map(|(name, _)| name)
+        .take_while(|name| name != b"IDAT")
+        .any(|name| &name == b"acTL")
+}
+
+/// `(type, raw chunk including length and CRC)` for each chunk after the signature.
+fn chunks(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 8;
+    std::iter::from_fn(move || {
+        let header = data.get(pos..pos + 8)?;
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let raw = data.get(pos..pos + 12 + len)?;
+        pos += raw.len();
+        Some(([header[4], header[5], header[6], header[7]], raw))
+    })
+}
@@ -0,0 +1,139 @@
+use serde::Serialize;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Idle,
+    Running,
+    Paused,
+}
+
+/// Snapshot of the current batch, returned by `get_job_status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub state: JobState,
+    pub total: usize,
+    pub done: usize,
+    /// Images currently being compressed. Stays above zero for a while after
+    /// pausing, until the in-flight images finish.
+    pub in_flight: usize,
+}
+
+struct Inner {
+    status: JobStatus,
+    started_at: Option<Instant>,
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+}
+
+/// Shared pause/resume control for the running batch (Tauri managed state).
+pub struct JobControl {
+    inner: Mutex<Inner>,
+    /// Signalled on resume and whenever an in-flight image finishes.
+    changed: Condvar,
+}
+
+impl Default for JobControl {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                status: JobStatus {
+                    state: JobState::Idle,
+                    total: 0,
+                    done: 0,
+                    in_flight: 0,
+                },
+                started_at: None,
+                paused_at: None,
+                paused_total: Duration::ZERO,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+}
+
+impl JobControl {
+    pub fn begin(&self, total: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.status = JobStatus {
+            state: JobState::Running,
+            total,
+            done: 0,
+            in_flight: 0,
+        };
+        inner.started_at = Some(Instant::now());
+        inner.paused_at = None;
+        inner.paused_total = Duration::ZERO;
+    }
+
+    pub fn finish(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.status.state = JobState::Idle;
+        inner.status.in_flight = 0;
+        inner.paused_at = None;
+    }
+
+    /// Hold the rest of the queue. Returns false if no batch is running.
+    pub fn pause(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.status.state != JobState::Running {
+            return false;
+        }
+        inner.status.state = JobState::Paused;
+        inner.paused_at = Some(Instant::now());
+        true
+    }
+
+    /// Release the queue. Returns false if the batch wasn't paused.
+    pub fn resume(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.status.state != JobState::Paused {
+            return false;
+        }
+        if let Some(paused_at) = inner.paused_at.take() {
+            inner.paused_total += paused_at.elapsed();
+        }
+        inner.status.state = JobState::Running;
+        self.changed.notify_all();
+        true
+    }
+
+    pub fn status(&self) -> JobStatus {
+        self.inner.lock().unwrap().status.clone()
+    }
+
+    /// Wait until the batch is running and fewer than `limit` images are in
+    /// flight, then count one more. Called by the dispatcher before handing
+    /// each image to the pool, so pausing holds the queue, not the workers.
+    pub fn acquire(&self, limit: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        while inner.status.state == JobState::Paused || inner.status.in_flight >= limit.max(1) {
+            inner = self.changed.wait(inner).unwrap();
+        }
+        inner.status.in_flight += 1;
+    }
+
+    /// Mark one in-flight image as finished. Returns the number of finished images.
+    pub fn release(&self) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        inner.status.in_flight = inner.status.in_flight.saturating_sub(1);
+        inner.status.done += 1;
+        // A worker is free for the dispatcher
+        self.changed.notify_all();
+        inner.status.done
+    }
+
+    /// Wall time since `begin`, excluding time spent paused.
+    pub fn active_elapsed(&self) -> Duration {
+        let inner = self.inner.lock().unwrap();
+        let Some(started_at) = inner.started_at else {
+            return Duration::ZERO;
+        };
+        let paused_now = inner.paused_at.map(|p| p.elapsed()).unwrap_or_default();
+        started_at
+            .elapsed()
+            .saturating_sub(inner.paused_total + paused_now)
+    }
+}
@@ -0,0 +1,188 @@
+use crate::commands::{CompressionSettings, ImageFileInfo};
+use crate::compressor::{self, CompressionResult};
+use crate::utils::ImageFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const JOURNAL_FILE: &str = "queue.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Pending,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalItem {
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub format: ImageFormat,
+    pub output_path: String,
+    pub status: ItemStatus,
+}
+
+/// One line of the journal. The header is written once when the batch starts,
+/// followed by one `Result` line per finished image. An append-only log keeps
+/// each update to a single small write, and a line torn by a crash is ignored.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Entry {
+    Header {
+        settings: CompressionSettings,
+        items: Vec<JournalItem>,
+    },
+    Result {
+        id: String,
+        status: ItemStatus,
+        output_path: String,
+    },
+}
+
+/// Journal of the running batch, stored in the app data directory so an
+/// interrupted batch can be resumed on the next launch.
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl Journal {
+    pub fn start(
+        data_dir: &Path,
+        settings: &CompressionSettings,
+        images: &[ImageFileInfo],
+        output_dir: &Path,
+    ) -> Result<Self, String> {
+        std::fs::create_dir_all(data_dir)
+            .map_err(|e| format!("앱 데이터 폴더 생성 실패: {}", e))?;
+        let path = data_dir.join(JOURNAL_FILE);
+
+        let items = images
+            .iter()
+            .map(|img| JournalItem {
+                id: img.id.clone(),
+                path: img.path.clone(),
+                name: img.name.clone(),
+                size: img.size,
+                format: img.format,
+                output_path: output_dir
+                    .join(compressor::output_name(
+                        &img.name,
+                        img.format,
+                        &settings.options,
+                    ))
+                    .to_string_lossy()
+                    .to_string(),
+                status: ItemStatus::Pending,
+            })
+            .collect();
+
+        let mut file = File::create(&path).map_err(|e| format!("작업 기록 생성 실패: {}", e))?;
+        write_entry(
+            &mut file,
+            &Entry::Header {
+                settings: settings.clone(),
+                items,
+            },
+        )?;
+
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn record(&self, id: &str, result: &CompressionResult) {
+        let entry = Entry::Result {
+            id: id.to_string(),
+            status: if result.success {
+                ItemStatus::Done
+            } else {
+                ItemStatus::Failed
+            },
+            output_path: result.output_path.clone(),
+        };
+        if let Ok(mut file) = self.file.lock() {
+            let _ = write_entry(&mut file, &entry);
+        }
+    }
+
+    /// The batch ran to the end; nothing is left to resume.
+    pub fn finish(self) {
+        drop(self.file);
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn write_entry(file: &mut File, entry: &Entry) -> Result<(), String> {
+    let mut line =
+        serde_json::to_vec(entry).map_err(|e| format!("작업 기록 직렬화 실패: {}", e))?;
+    line.push(b'\n');
+    file.write_all(&line)
+        .and_then(|_| file.sync_data())
+        .map_err(|e| format!("작업 기록 쓰기 실패: {}", e))
+}
+
+/// Batch state recovered from a previous run's journal.
+pub struct RecoveredJob {
+    pub settings: CompressionSettings,
+    pub items: Vec<JournalItem>,
+}
+
+/// Replay the journal left behind by an interrupted batch, if any.
+pub fn load(data_dir: &Path) -> Option<RecoveredJob> {
+    let file = File::open(data_dir.join(JOURNAL_FILE)).ok()?;
+    let mut lines = BufReader::new(file).lines().map_while(Result::ok);
+
+    let Some(Ok(Entry::Header {
+        settings,
+        mut items,
+    })) = lines.next().map(|l| serde_json::from_str::<Entry>(&l))
+    else {
+        return None;
+    };
+
+    let index: HashMap<String, usize> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (item.id.clone(), i))
+        .collect();
+
+    for line in lines {
+        // A torn last line from a crash simply doesn't parse
+        if let Ok(Entry::Result {
+            id,
+            status,
+            output_path,
+        }) = serde_json::from_str::<Entry>(&line)
+        {
+            if let Some(&i) = index.get(&id) {
+                items[i].status = status;
+                items[i].output_path = output_path;
+            }
+        }
+    }
+
+    Some(RecoveredJob { settings, items })
+}
+
+pub fn discard(data_dir: &Path) {
+    let _ = std::fs::remove_file(data_dir.join(JOURNAL_FILE));
+}
+
+/// Whether an output left behind by an interrupted run is usable. Outputs are
+/// only renamed into place once complete, so decoding just guards against a
+/// file that was damaged or replaced since.
+pub fn output_is_valid(output_path: &Path) -> bool {
+    image::ImageReader::open(output_path)
+        .and_then(|r| r.with_guessed_format())
+        .map(|r| r.decode().is_ok())
+        .unwrap_or(false)
+}
@@ -1,16 +1,27 @@
+mod cache;
 mod commands;
 mod compressor;
+mod job;
+mod journal;
 mod thumbnail;
 mod utils;
+mod worker;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .manage(job::JobControl::default())
+        .manage(worker::CliOverrides::parse(std::env::args().skip(1)))
         .invoke_handler(tauri::generate_handler![
             commands::load_images,
             commands::compress_images,
+            commands::pause_compression,
+            commands::resume_compression,
+            commands::get_job_status,
+            commands::get_resumable_job,
+            commands::discard_resumable_job,
             commands::open_output_folder,
             commands::get_default_output_dir,
             commands::get_image_preview,
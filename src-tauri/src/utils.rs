@@ -33,3 +33,11 @@ pub fn ensure_output_dir(path: &Path) -> std::io::Result<()> {
     }
     Ok(())
 }
+
+/// Whether both paths name the same existing file.
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
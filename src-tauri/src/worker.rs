@@ -0,0 +1,131 @@
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// Build the rayon pool a compression batch runs on.
+///
+/// `max_workers == 0` keeps rayon's default of one thread per core.
+/// In `background` mode every worker lowers its own OS scheduling priority
+/// on start, so the rest of the system stays responsive during big batches.
+pub fn build_pool(max_workers: usize, background: bool) -> Result<ThreadPool, String> {
+    let mut builder = ThreadPoolBuilder::new().thread_name(|i| format!("picslim-worker-{}", i));
+
+    if max_workers > 0 {
+        builder = builder.num_threads(max_workers);
+    }
+    if background {
+        builder = builder.start_handler(|_| lower_current_thread_priority());
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("작업 스레드 풀 생성 실패: {}", e))
+}
+
+/// Worker options given on the command line, e.g.
+/// `pic-slim --max-workers 2 --background`. They win over the batch's
+/// `CompressionSettings`, so a scripted launch stays throttled whatever the
+/// saved settings say.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliOverrides {
+    pub max_workers: Option<usize>,
+    pub background: bool,
+}
+
+impl CliOverrides {
+    /// Pick out `--max-workers <n>` (or `--max-workers=<n>`) and
+    /// `--background`; other arguments are ignored. An invalid count is ignored too.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut overrides = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let count = match arg.as_str() {
+                "--background" => {
+                    overrides.background = true;
+                    continue;
+                }
+                "--max-workers" => args.next(),
+                _ => match arg.strip_prefix("--max-workers=") {
+                    Some(count) => Some(count.to_string()),
+                    None => continue,
+                },
+            };
+            if let Some(count) = count.and_then(|c| c.parse().ok()) {
+                overrides.max_workers = Some(count);
+            }
+        }
+        overrides
+    }
+
+    /// The `(max_workers, background)` a batch runs with.
+    pub fn apply(&self, max_workers: usize, background: bool) -> (usize, bool) {
+        (
+            self.max_workers.unwrap_or(max_workers),
+            background || self.background,
+        )
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn lower_current_thread_priority() {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_MODE_BACKGROUND_BEGIN,
+    };
+
+    // Background mode also lowers the thread's I/O and memory priority.
+    unsafe {
+        SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn lower_current_thread_priority() {
+    // Linux keeps nice values per thread, so PRIO_PROCESS with who = 0
+    // only affects the calling worker, not the UI thread.
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, 10);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn lower_current_thread_priority() {
+    unsafe {
+        libc::pthread_set_qos_class_self_np(libc::qos_class_t::QOS_CLASS_UTILITY, 0);
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn lower_current_thread_priority() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> CliOverrides {
+        CliOverrides::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_worker_flags() {
+        assert_eq!(parse(&[]), CliOverrides::default());
+        assert_eq!(
+            parse(&["--max-workers", "2", "--background"]),
+            CliOverrides {
+                max_workers: Some(2),
+                background: true,
+            }
+        );
+        assert_eq!(parse(&["--max-workers=3"]).max_workers, Some(3));
+        assert_eq!(parse(&["--max-workers", "many"]).max_workers, None);
+        assert_eq!(
+            parse(&["--other", "--max-workers"]),
+            CliOverrides::default()
+        );
+    }
+
+    #[test]
+    fn command_line_wins_over_settings() {
+        let overrides = parse(&["--max-workers", "2"]);
+        assert_eq!(overrides.apply(8, false), (2, false));
+        assert_eq!(overrides.apply(8, true), (2, true));
+        assert_eq!(parse(&["--background"]).apply(4, false), (4, true));
+    }
+}
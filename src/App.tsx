@@ -1,6 +1,11 @@
-import { useState, useCallback } from "react";
-import type { ImageItem, AppState } from "./types";
-import { loadImages, openOutputFolder } from "./lib/tauri";
+import { useState, useCallback, useEffect } from "react";
+import type { ImageItem, AppState, ResumableJob } from "./types";
+import {
+  loadImages,
+  openOutputFolder,
+  getResumableJob,
+  discardResumableJob,
+} from "./lib/tauri";
 import { useSettings } from "./hooks/useSettings";
 import { useImageList } from "./hooks/useImageList";
 import { useCompression } from "./hooks/useCompression";
@@ -11,19 +16,35 @@ import ImageList from "./components/ImageList";
 import ActionBar from "./components/ActionBar";
 import Settings from "./components/Settings";
 import PreviewModal from "./components/PreviewModal";
+import ResumePrompt from "./components/ResumePrompt";
 
 function App() {
-  const { settings, updateQuality, updateOutputDir } = useSettings();
+  const {
+    settings,
+    updateQuality,
+    updateOutputDir,
+    updateJpeg,
+    updateWorkers,
+  } = useSettings();
   const {
     images,
     addImages,
     removeImage,
     clearImages,
     updateImageStatus,
+    updateImageProgress,
     updateImageResult,
   } = useImageList();
-  const { isCompressing, progress, startCompression } = useCompression(
+  const {
+    isCompressing,
+    progress,
+    jobStatus,
+    startCompression,
+    pause,
+    resume,
+  } = useCompression(
     updateImageStatus,
+    updateImageProgress,
     updateImageResult,
   );
 
@@ -31,6 +52,17 @@ function App() {
   const [isLoading, setIsLoading] = useState(false);
   const [settingsOpen, setSettingsOpen] = useState(false);
   const [previewImage, setPreviewImage] = useState<ImageItem | null>(null);
+  const [resumableJob, setResumableJob] = useState<ResumableJob | null>(null);
+
+  // A batch interrupted last time must be resumed or discarded before a new
+  // one starts, since starting a batch replaces its journal
+  useEffect(() => {
+    getResumableJob()
+      .then(setResumableJob)
+      .catch(() => {
+        /* 작업 기록 확인 실패 시 무시 */
+      });
+  }, []);
 
   const handleFiles = useCallback(
     async (paths: string[]) => {
@@ -57,6 +89,25 @@ function App() {
     await startCompression(images, settings);
   }, [images, settings, startCompression]);
 
+  const handleResume = useCallback(async () => {
+    if (!resumableJob) return;
+    setResumableJob(null);
+    addImages(resumableJob.images);
+    await startCompression(
+      resumableJob.images.map((img) => ({ ...img, status: "pending" as const })),
+      resumableJob.settings,
+    );
+  }, [resumableJob, addImages, startCompression]);
+
+  const handleDiscardResume = useCallback(async () => {
+    setResumableJob(null);
+    try {
+      await discardResumableJob();
+    } catch {
+      /* 작업 기록 삭제 실패 시 무시 */
+    }
+  }, []);
+
   const handleOpenFolder = useCallback(async () => {
     try {
       await openOutputFolder(settings.output_dir);
@@ -100,8 +151,11 @@ function App() {
         <ActionBar
           appState={appState}
           progress={progress}
+          jobStatus={jobStatus}
           outputDir={settings.output_dir}
           onCompress={handleCompress}
+          onPause={pause}
+          onResume={resume}
           onAddFiles={handleFiles}
           onOpenFolder={handleOpenFolder}
           onClear={handleClear}
@@ -112,9 +166,14 @@ function App() {
         isOpen={settingsOpen}
         quality={settings.quality}
         outputDir={settings.output_dir}
+        jpeg={settings.jpeg ?? {}}
+        maxWorkers={settings.max_workers ?? 0}
+        background={settings.background ?? false}
         onClose={() => setSettingsOpen(false)}
         onQualityChange={updateQuality}
         onOutputDirChange={updateOutputDir}
+        onJpegChange={updateJpeg}
+        onWorkersChange={updateWorkers}
       />
 
       <PreviewModal
@@ -122,6 +181,12 @@ function App() {
         onClose={() => setPreviewImage(null)}
       />
 
+      <ResumePrompt
+        job={resumableJob}
+        onResume={handleResume}
+        onDiscard={handleDiscardResume}
+      />
+
       {/* Loading overlay */}
       {isLoading && (
         <div
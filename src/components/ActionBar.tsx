@@ -1,12 +1,15 @@
 import { open } from "@tauri-apps/plugin-dialog";
-import type { AppState } from "../types";
+import type { AppState, JobStatus } from "../types";
 import ProgressBar from "./ProgressBar";
 
 interface ActionBarProps {
   appState: AppState;
-  progress: { done: number; total: number };
+  progress: { done: number; total: number; etaMs?: number | null };
+  jobStatus: JobStatus | null;
   outputDir: string;
   onCompress: () => void;
+  onPause: () => void;
+  onResume: () => void;
   onAddFiles: (paths: string[]) => void;
   onOpenFolder: () => void;
   onClear: () => void;
@@ -15,8 +18,11 @@ interface ActionBarProps {
 export default function ActionBar({
   appState,
   progress,
+  jobStatus,
   outputDir,
   onCompress,
+  onPause,
+  onResume,
   onAddFiles,
   onOpenFolder,
   onClear,
@@ -37,6 +43,8 @@ export default function ActionBar({
     }
   };
 
+  const isPaused = jobStatus?.state === "paused";
+
   return (
     <div
       style={{
@@ -50,7 +58,53 @@ export default function ActionBar({
       }}
     >
       {appState === "compressing" ? (
-        <ProgressBar done={progress.done} total={progress.total} />
+        <>
+          <ProgressBar
+            done={progress.done}
+            total={progress.total}
+            etaMs={progress.etaMs}
+            paused={isPaused}
+            inFlight={jobStatus?.in_flight}
+          />
+          <button
+            type="button"
+            onClick={isPaused ? onResume : onPause}
+            className="action-btn-secondary"
+            style={{
+              display: "flex",
+              alignItems: "center",
+              gap: 6,
+              padding: "8px 14px",
+              fontSize: 12,
+              fontWeight: 500,
+              color: isPaused ? "#2563EB" : "#6B7280",
+              border: "1px solid #E5E7EB",
+              borderRadius: 10,
+              background: "transparent",
+              cursor: "pointer",
+              flexShrink: 0,
+              transition: "all 0.2s ease",
+            }}
+          >
+            <svg
+              width="13"
+              height="13"
+              viewBox="0 0 24 24"
+              fill="currentColor"
+              aria-hidden="true"
+            >
+              {isPaused ? (
+                <path d="M7 4l13 8-13 8z" />
+              ) : (
+                <>
+                  <rect x="6" y="4" width="4" height="16" rx="1" />
+                  <rect x="14" y="4" width="4" height="16" rx="1" />
+                </>
+              )}
+            </svg>
+            {isPaused ? "재개" : "일시정지"}
+          </button>
+        </>
       ) : appState === "done" ? (
         <>
           <button
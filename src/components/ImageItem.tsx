@@ -1,4 +1,4 @@
-import type { ImageItem as ImageItemType } from "../types";
+import type { ImageItem as ImageItemType, CompressionStage } from "../types";
 
 interface ImageItemProps {
   image: ImageItemType;
@@ -14,6 +14,20 @@ const formatColors: Record<string, { bg: string; color: string; border: string }
 
 const defaultColors = { bg: "#F9FAFB", color: "#6B7280", border: "#E5E7EB" };
 
+const stageLabels: Record<CompressionStage, string> = {
+  decode: "디코딩",
+  quantize: "양자화",
+  encode: "인코딩",
+  optimize: "최적화",
+};
+
+// oxipng and the JPEG finish pass only report when the optimize stage starts
+// and ends, so a percentage there would sit at 0% for the whole run.
+const stageText = (stage: CompressionStage, progress = 0) =>
+  stage === "optimize"
+    ? `${stageLabels[stage]} 중…`
+    : `${stageLabels[stage]} ${Math.round(progress * 100)}%`;
+
 export default function ImageItem({
   image,
   onRemove,
@@ -23,6 +37,30 @@ export default function ImageItem({
   const badge = formatColors[normalizedFormat] || defaultColors;
   const displayFormat = normalizedFormat === "Jpeg" ? "JPG" : normalizedFormat.toUpperCase();
 
+  // Results reused from an earlier run report the status they had then; an
+  // already-optimal one comes back with its original size
+  const reused = image.result_status === "skipped";
+  const keptOriginal =
+    image.result_status === "kept_original" ||
+    (reused && image.compressed_size === image.size);
+
+  const reusedTag = (
+    <span
+      style={{
+        padding: "1px 6px",
+        fontSize: 10,
+        fontWeight: 600,
+        color: "#6B7280",
+        background: "#F3F4F6",
+        borderRadius: 4,
+        lineHeight: "16px",
+      }}
+      title="이전에 같은 설정으로 압축한 결과를 재사용했습니다"
+    >
+      재사용
+    </span>
+  );
+
   const statusIndicator = () => {
     switch (image.status) {
       case "pending":
@@ -51,12 +89,57 @@ export default function ImageItem({
                 strokeLinecap="round"
               />
             </svg>
-            <span style={{ fontSize: 12, color: "#2563EB", fontWeight: 500 }}>
-              압축 중
+            <span
+              style={{
+                fontSize: 12,
+                color: "#2563EB",
+                fontWeight: 500,
+                fontVariantNumeric: "tabular-nums",
+              }}
+            >
+              {image.stage
+                ? stageText(image.stage, image.stage_progress)
+                : "압축 중"}
             </span>
           </div>
         );
       case "done":
+        if (keptOriginal) {
+          return (
+            <div
+              className="animate-fade-in"
+              style={{ display: "flex", alignItems: "center", gap: 6 }}
+              title="더 줄일 수 없어 원본을 그대로 두었습니다"
+            >
+              <div
+                style={{
+                  width: 20,
+                  height: 20,
+                  borderRadius: "50%",
+                  background: "#9CA3AF",
+                  display: "flex",
+                  alignItems: "center",
+                  justifyContent: "center",
+                }}
+              >
+                <svg width="10" height="10" viewBox="0 0 24 24" fill="none">
+                  <path
+                    d="M5 12h14"
+                    stroke="#fff"
+                    strokeWidth="3"
+                    strokeLinecap="round"
+                  />
+                </svg>
+              </div>
+              <span
+                style={{ fontSize: 12, color: "#6B7280", fontWeight: 500 }}
+              >
+                이미 최적
+              </span>
+              {reused && reusedTag}
+            </div>
+          );
+        }
         return (
           <div
             className="animate-fade-in"
@@ -96,6 +179,7 @@ export default function ImageItem({
                 {image.ratio}%
               </span>
             )}
+            {reused && reusedTag}
           </div>
         );
       case "error":
@@ -256,7 +340,7 @@ export default function ImageItem({
                 style={{
                   fontSize: 11,
                   fontWeight: 600,
-                  color: "#16A34A",
+                  color: keptOriginal ? "#9CA3AF" : "#16A34A",
                   fontVariantNumeric: "tabular-nums",
                 }}
               >
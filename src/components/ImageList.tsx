@@ -25,6 +25,15 @@ export default function ImageList({
       ? Math.max(0, Math.round(((doneOriginal - totalCompressed) / doneOriginal) * 100))
       : 0;
 
+  const keptCount = doneImages.filter(
+    (img) =>
+      img.result_status === "kept_original" ||
+      (img.result_status === "skipped" && img.compressed_size === img.size),
+  ).length;
+  const reusedCount = doneImages.filter(
+    (img) => img.result_status === "skipped",
+  ).length;
+
   const allDone =
     images.length > 0 &&
     images.every((img) => img.status === "done" || img.status === "error");
@@ -80,6 +89,22 @@ export default function ImageList({
             >
               {formatSize(totalOriginal)}
             </span>
+            {keptCount > 0 && (
+              <span
+                style={{ fontSize: 12, color: "#6B7280" }}
+                title="더 줄일 수 없어 원본을 그대로 둔 이미지"
+              >
+                이미 최적 {keptCount}개
+              </span>
+            )}
+            {reusedCount > 0 && (
+              <span
+                style={{ fontSize: 12, color: "#6B7280" }}
+                title="이전 결과를 재사용한 이미지"
+              >
+                재사용 {reusedCount}개
+              </span>
+            )}
           </div>
           {doneImages.length > 0 && savedPercent > 0 && (
             <div
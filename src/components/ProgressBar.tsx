@@ -1,6 +1,12 @@
 import type { ProgressBarProps } from "../types";
 
-export default function ProgressBar({ done, total }: ProgressBarProps) {
+export default function ProgressBar({
+  done,
+  total,
+  etaMs,
+  paused = false,
+  inFlight = 0,
+}: ProgressBarProps) {
   const percent = total > 0 ? Math.min(100, Math.max(0, Math.round((done / total) * 100))) : 0;
   const isIndeterminate = total > 0 && done === 0;
   const isComplete = total > 0 && done === total;
@@ -14,8 +20,8 @@ export default function ProgressBar({ done, total }: ProgressBarProps) {
         flex: 1,
       }}
     >
-      {/* Spinner - 완료 시 숨김 */}
-      {!isComplete && (
+      {/* Spinner - 완료 시, 일시정지 후 진행 중인 이미지가 없을 때 숨김 */}
+      {!isComplete && !(paused && inFlight === 0) && (
         <svg
           className="animate-spin motion-reduce:animate-none"
           style={{ width: 18, height: 18, color: "#2563EB", flexShrink: 0 }}
@@ -81,6 +87,39 @@ export default function ProgressBar({ done, total }: ProgressBarProps) {
       >
         {isIndeterminate ? `${total}개 압축 중…` : `${done}/${total}`}
       </span>
+      {paused ? (
+        <span
+          style={{
+            fontSize: 11,
+            fontWeight: 500,
+            color: "#D97706",
+            flexShrink: 0,
+            whiteSpace: "nowrap",
+          }}
+        >
+          {inFlight > 0 ? `일시정지 중 · ${inFlight}개 마무리 중` : "일시정지됨"}
+        </span>
+      ) : !isComplete && etaMs != null && (
+        <span
+          style={{
+            fontSize: 11,
+            color: "#9CA3AF",
+            fontVariantNumeric: "tabular-nums",
+            flexShrink: 0,
+            whiteSpace: "nowrap",
+          }}
+        >
+          {formatEta(etaMs)}
+        </span>
+      )}
     </div>
   );
 }
+
+function formatEta(ms: number): string {
+  const seconds = Math.ceil(ms / 1000);
+  if (seconds < 60) return `약 ${seconds}초 남음`;
+  const minutes = Math.round(seconds / 60);
+  if (minutes < 60) return `약 ${minutes}분 남음`;
+  return `약 ${Math.floor(minutes / 60)}시간 ${minutes % 60}분 남음`;
+}
@@ -0,0 +1,108 @@
+import type { ResumableJob } from "../types";
+
+interface ResumePromptProps {
+  job: ResumableJob | null;
+  onResume: () => void;
+  onDiscard: () => void;
+}
+
+export default function ResumePrompt({
+  job,
+  onResume,
+  onDiscard,
+}: ResumePromptProps) {
+  if (!job) return null;
+
+  const remaining = job.images.length;
+  const total = remaining + job.finished;
+
+  return (
+    <div
+      className="animate-fade-in"
+      role="dialog"
+      aria-modal="true"
+      aria-labelledby="resume-title"
+      style={{
+        position: "fixed",
+        inset: 0,
+        background: "rgba(0,0,0,0.3)",
+        backdropFilter: "blur(2px)",
+        zIndex: 50,
+        display: "flex",
+        alignItems: "center",
+        justifyContent: "center",
+        padding: 32,
+      }}
+    >
+      <div
+        style={{
+          background: "#fff",
+          borderRadius: 16,
+          boxShadow: "0 24px 48px rgba(0,0,0,0.2), 0 4px 12px rgba(0,0,0,0.1)",
+          maxWidth: 400,
+          width: "100%",
+          padding: "28px 28px 24px",
+        }}
+      >
+        <h2
+          id="resume-title"
+          style={{ fontSize: 15, fontWeight: 700, color: "#111827" }}
+        >
+          완료되지 않은 작업이 있습니다
+        </h2>
+        <p
+          style={{
+            marginTop: 10,
+            fontSize: 13,
+            lineHeight: "20px",
+            color: "#4B5563",
+          }}
+        >
+          지난번 작업이 중간에 종료되었습니다. {total}개 중 {job.finished}개가
+          완료되었고, 남은 {remaining}개를 이어서 압축할 수 있습니다.
+        </p>
+        <p style={{ marginTop: 6, fontSize: 11, color: "#9CA3AF" }}>
+          버리면 작업 기록이 삭제되어 나중에 이어서 할 수 없습니다.
+        </p>
+
+        <div
+          className="flex justify-end"
+          style={{ gap: 10, marginTop: 24 }}
+        >
+          <button
+            type="button"
+            onClick={onDiscard}
+            className="cursor-pointer"
+            style={{
+              padding: "9px 16px",
+              fontSize: 12,
+              fontWeight: 500,
+              color: "#4B5563",
+              border: "1px solid #E5E7EB",
+              borderRadius: 8,
+              background: "transparent",
+            }}
+          >
+            버리기
+          </button>
+          <button
+            type="button"
+            onClick={onResume}
+            className="cursor-pointer"
+            style={{
+              padding: "9px 16px",
+              fontSize: 12,
+              fontWeight: 600,
+              color: "#fff",
+              border: "none",
+              borderRadius: 8,
+              background: "#2563EB",
+            }}
+          >
+            이어서 압축
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -1,5 +1,12 @@
+import { useState } from "react";
 import { open } from "@tauri-apps/plugin-dialog";
 import { version } from "../../package.json";
+import type {
+  ChromaSubsampling,
+  CompressionSettings,
+  JpegOptions,
+  QuantTablePreset,
+} from "../types";
 
 const ENGINES = [
   { fmt: "JPG", engine: "mozjpeg", desc: "프로그레시브 인코딩", color: "#F59E0B" },
@@ -7,23 +14,66 @@ const ENGINES = [
   { fmt: "GIF", engine: "gif", desc: "프레임 최적화", color: "#8B5CF6" },
 ] as const;
 
+const SUBSAMPLING_OPTIONS: { value: ChromaSubsampling; label: string }[] = [
+  { value: "auto", label: "자동 (스크린샷은 4:4:4)" },
+  { value: "yuv420", label: "4:2:0 - 작은 파일" },
+  { value: "yuv422", label: "4:2:2" },
+  { value: "yuv444", label: "4:4:4 - 선명한 색상" },
+];
+
+const QUANT_TABLE_OPTIONS: { value: QuantTablePreset; label: string }[] = [
+  { value: "default", label: "기본 (mozjpeg)" },
+  { value: "annex_k", label: "JPEG 표준 (Annex K)" },
+  { value: "flat", label: "평탄" },
+  { value: "ms_ssim", label: "MS-SSIM 최적화" },
+  { value: "psnr_hvs", label: "PSNR-HVS 최적화" },
+];
+
+// Up to one worker per logical core, the backend's default
+const CORE_COUNT = navigator.hardwareConcurrency || 4;
+
+const fieldLabelStyle = { fontSize: 12, color: "#4B5563" } as const;
+
+const selectStyle = {
+  padding: "6px 10px",
+  fontSize: 12,
+  color: "#111827",
+  background: "#FAFAFA",
+  border: "1px solid #E5E7EB",
+  borderRadius: 8,
+} as const;
+
 interface SettingsProps {
   isOpen: boolean;
   quality: number;
   outputDir: string;
+  jpeg: JpegOptions;
+  maxWorkers: number;
+  background: boolean;
   onClose: () => void;
   onQualityChange: (quality: number) => void;
   onOutputDirChange: (dir: string) => void;
+  onJpegChange: (jpeg: Partial<JpegOptions>) => void;
+  onWorkersChange: (
+    workers: Pick<CompressionSettings, "max_workers" | "background">,
+  ) => void;
 }
 
 export default function Settings({
   isOpen,
   quality,
   outputDir,
+  jpeg,
+  maxWorkers,
+  background,
   onClose,
   onQualityChange,
   onOutputDirChange,
+  onJpegChange,
+  onWorkersChange,
 }: SettingsProps) {
+  const [jpegOpen, setJpegOpen] = useState(false);
+
   const handleSelectFolder = async () => {
     const selected = await open({
       directory: true,
@@ -59,6 +109,8 @@ export default function Settings({
           transform: isOpen ? "translateX(0)" : "translateX(100%)",
           transition: "transform 0.3s cubic-bezier(0.16, 1, 0.3, 1)",
           visibility: isOpen ? "visible" : "hidden",
+          display: "flex",
+          flexDirection: "column",
         }}
       >
         {/* Header */}
@@ -83,8 +135,8 @@ export default function Settings({
           </button>
         </div>
 
-        {/* Content */}
-        <div style={{ padding: "28px 32px" }}>
+        {/* Content - scrolls above the footer once the sections are open */}
+        <div style={{ flex: 1, overflowY: "auto", padding: "28px 32px 96px" }}>
           {/* Quality slider */}
           <div style={{ marginBottom: 32 }}>
             <div className="flex items-center justify-between" style={{ marginBottom: 16 }}>
@@ -155,6 +207,154 @@ export default function Settings({
             </div>
           </div>
 
+          {/* Workers */}
+          <div style={{ marginBottom: 32 }}>
+            <label style={{ display: "block", fontSize: 13, fontWeight: 600, color: "#111827", marginBottom: 14 }}>
+              작업 속도
+            </label>
+            <div style={{ display: "flex", flexDirection: "column", gap: 14 }}>
+              <div className="flex items-center justify-between">
+                <label htmlFor="max-workers" style={fieldLabelStyle}>
+                  동시 작업 수
+                </label>
+                <select
+                  id="max-workers"
+                  value={maxWorkers}
+                  onChange={(e) => onWorkersChange({ max_workers: Number(e.target.value) })}
+                  className="cursor-pointer"
+                  style={selectStyle}
+                >
+                  <option value={0}>자동 (코어 수만큼)</option>
+                  {Array.from({ length: CORE_COUNT }, (_, i) => i + 1).map((n) => (
+                    <option key={n} value={n}>
+                      {n}개
+                    </option>
+                  ))}
+                </select>
+              </div>
+
+              <label className="flex items-center justify-between cursor-pointer" style={fieldLabelStyle}>
+                <span>
+                  백그라운드 모드
+                  <span style={{ display: "block", marginTop: 2, fontSize: 10, color: "#9CA3AF" }}>
+                    우선순위를 낮춰 다른 작업이 느려지지 않게 합니다
+                  </span>
+                </span>
+                <input
+                  type="checkbox"
+                  checked={background}
+                  onChange={(e) => onWorkersChange({ background: e.target.checked })}
+                  className="cursor-pointer"
+                  style={{ accentColor: "#2563EB" }}
+                />
+              </label>
+            </div>
+          </div>
+
+          {/* Advanced JPEG */}
+          <div style={{ marginBottom: 32 }}>
+            <button
+              type="button"
+              onClick={() => setJpegOpen((v) => !v)}
+              aria-expanded={jpegOpen}
+              className="flex items-center justify-between w-full cursor-pointer"
+              style={{ fontSize: 13, fontWeight: 600, color: "#111827", background: "transparent" }}
+            >
+              JPEG 고급 설정
+              <svg
+                width="14"
+                height="14"
+                viewBox="0 0 24 24"
+                fill="none"
+                stroke="#9CA3AF"
+                strokeWidth="2"
+                strokeLinecap="round"
+                strokeLinejoin="round"
+                style={{ transform: jpegOpen ? "rotate(180deg)" : "none", transition: "transform 0.2s" }}
+              >
+                <polyline points="6 9 12 15 18 9" />
+              </svg>
+            </button>
+
+            {jpegOpen && (
+              <div style={{ display: "flex", flexDirection: "column", gap: 14, marginTop: 16 }}>
+                <div className="flex items-center justify-between">
+                  <label htmlFor="jpeg-subsampling" style={fieldLabelStyle}>
+                    크로마 서브샘플링
+                  </label>
+                  <select
+                    id="jpeg-subsampling"
+                    value={jpeg.chroma_subsampling ?? "auto"}
+                    onChange={(e) =>
+                      onJpegChange({ chroma_subsampling: e.target.value as ChromaSubsampling })
+                    }
+                    className="cursor-pointer"
+                    style={selectStyle}
+                  >
+                    {SUBSAMPLING_OPTIONS.map(({ value, label }) => (
+                      <option key={value} value={value}>
+                        {label}
+                      </option>
+                    ))}
+                  </select>
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <label htmlFor="jpeg-scan" style={fieldLabelStyle}>
+                    스캔 방식
+                  </label>
+                  <select
+                    id="jpeg-scan"
+                    value={(jpeg.progressive ?? true) ? "progressive" : "baseline"}
+                    onChange={(e) => onJpegChange({ progressive: e.target.value === "progressive" })}
+                    className="cursor-pointer"
+                    style={selectStyle}
+                  >
+                    <option value="progressive">프로그레시브</option>
+                    <option value="baseline">베이스라인</option>
+                  </select>
+                </div>
+
+                <div className="flex items-center justify-between">
+                  <label htmlFor="jpeg-quant-table" style={fieldLabelStyle}>
+                    양자화 테이블
+                  </label>
+                  <select
+                    id="jpeg-quant-table"
+                    value={jpeg.quant_table ?? "default"}
+                    onChange={(e) =>
+                      onJpegChange({ quant_table: e.target.value as QuantTablePreset })
+                    }
+                    className="cursor-pointer"
+                    style={selectStyle}
+                  >
+                    {QUANT_TABLE_OPTIONS.map(({ value, label }) => (
+                      <option key={value} value={value}>
+                        {label}
+                      </option>
+                    ))}
+                  </select>
+                </div>
+
+                <label className="flex items-center justify-between cursor-pointer" style={fieldLabelStyle}>
+                  <span>
+                    트렐리스 양자화
+                    <span style={{ display: "block", marginTop: 2, fontSize: 10, color: "#9CA3AF" }}>
+                      끄면 빨라지지만 파일이 조금 커집니다
+                    </span>
+                  </span>
+                  <input
+                    type="checkbox"
+                    checked={jpeg.trellis ?? true}
+                    onChange={(e) => onJpegChange({ trellis: e.target.checked })}
+                    className="cursor-pointer"
+                    style={{ accentColor: "#2563EB" }}
+                  />
+                </label>
+              </div>
+            )}
+          </div>
+
           <div style={{ height: 1, background: "#F3F4F6", marginBottom: 28 }} />
 
           {/* Engine info */}
@@ -1,18 +1,72 @@
-import { useState, useCallback } from "react";
+import { useState, useCallback, useEffect } from "react";
 import type {
   ImageItem,
   CompressionSettings,
   CompressionEvent,
   CompressionResult,
+  CompressionStage,
+  JobStatus,
 } from "../types";
-import { compressImages } from "../lib/tauri";
+import {
+  compressImages,
+  pauseCompression,
+  resumeCompression,
+  getJobStatus,
+} from "../lib/tauri";
 
 export function useCompression(
   updateImageStatus: (id: string, status: ImageItem["status"]) => void,
+  updateImageProgress: (
+    id: string,
+    stage: CompressionStage,
+    progress: number,
+  ) => void,
   updateImageResult: (id: string, result: CompressionResult) => void,
 ) {
   const [isCompressing, setIsCompressing] = useState(false);
-  const [progress, setProgress] = useState({ done: 0, total: 0 });
+  const [progress, setProgress] = useState<{
+    done: number;
+    total: number;
+    etaMs?: number | null;
+  }>({ done: 0, total: 0 });
+  const [jobStatus, setJobStatus] = useState<JobStatus | null>(null);
+
+  const refreshJobStatus = useCallback(async () => {
+    try {
+      setJobStatus(await getJobStatus());
+    } catch {
+      /* 작업 상태 조회 실패 시 무시 */
+    }
+  }, []);
+
+  // Poll while running, so a pause shows when its in-flight images are done
+  useEffect(() => {
+    if (!isCompressing) {
+      setJobStatus(null);
+      return;
+    }
+    refreshJobStatus();
+    const timer = setInterval(refreshJobStatus, 1000);
+    return () => clearInterval(timer);
+  }, [isCompressing, refreshJobStatus]);
+
+  const pause = useCallback(async () => {
+    try {
+      await pauseCompression();
+    } catch {
+      /* 일시정지 실패 시 무시 */
+    }
+    await refreshJobStatus();
+  }, [refreshJobStatus]);
+
+  const resume = useCallback(async () => {
+    try {
+      await resumeCompression();
+    } catch {
+      /* 재개 실패 시 무시 */
+    }
+    await refreshJobStatus();
+  }, [refreshJobStatus]);
 
   const startCompression = useCallback(
     async (images: ImageItem[], settings: CompressionSettings) => {
@@ -29,19 +83,25 @@ export function useCompression(
           case "start":
             updateImageStatus(event.image_id, "compressing");
             break;
+          case "stage":
+            updateImageProgress(event.image_id, event.stage, event.progress);
+            break;
           case "complete":
-            if (event.result) {
-              updateImageResult(event.image_id, event.result);
-            }
+            updateImageResult(event.image_id, event.result);
             completed++;
-            setProgress({ done: completed, total: pendingImages.length });
+            setProgress((prev) => ({ ...prev, done: completed }));
             break;
           case "error":
-            if (event.result) {
-              updateImageResult(event.image_id, event.result);
-            }
+            updateImageResult(event.image_id, event.result);
             completed++;
-            setProgress({ done: completed, total: pendingImages.length });
+            setProgress((prev) => ({ ...prev, done: completed }));
+            break;
+          case "batch":
+            setProgress({
+              done: event.done,
+              total: event.total,
+              etaMs: event.eta_ms,
+            });
             break;
         }
       };
@@ -66,8 +126,15 @@ export function useCompression(
         setIsCompressing(false);
       }
     },
-    [updateImageStatus, updateImageResult],
+    [updateImageStatus, updateImageProgress, updateImageResult],
   );
 
-  return { isCompressing, progress, startCompression };
+  return {
+    isCompressing,
+    progress,
+    jobStatus,
+    startCompression,
+    pause,
+    resume,
+  };
 }
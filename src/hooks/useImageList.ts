@@ -1,5 +1,10 @@
 import { useState, useCallback } from "react";
-import type { ImageItem, ImageFileInfo, CompressionResult } from "../types";
+import type {
+  ImageItem,
+  ImageFileInfo,
+  CompressionResult,
+  CompressionStage,
+} from "../types";
 
 export function useImageList() {
   const [images, setImages] = useState<ImageItem[]>([]);
@@ -31,6 +36,19 @@ export function useImageList() {
     [],
   );
 
+  const updateImageProgress = useCallback(
+    (id: string, stage: CompressionStage, progress: number) => {
+      setImages((prev) =>
+        prev.map((img) =>
+          img.id === id && img.status === "compressing"
+            ? { ...img, stage, stage_progress: progress }
+            : img,
+        ),
+      );
+    },
+    [],
+  );
+
   const updateImageResult = useCallback(
     (id: string, result: CompressionResult) => {
       setImages((prev) =>
@@ -45,6 +63,9 @@ export function useImageList() {
             return {
               ...img,
               status: "done" as const,
+              result_status: result.status,
+              stage: undefined,
+              stage_progress: undefined,
               compressed_size: result.compressed_size,
               compressed_size_display: formatSize(result.compressed_size),
               output_path: result.output_path,
@@ -54,6 +75,9 @@ export function useImageList() {
             return {
               ...img,
               status: "error" as const,
+              result_status: result.status,
+              stage: undefined,
+              stage_progress: undefined,
               error: result.error || "알 수 없는 오류",
             };
           }
@@ -68,6 +92,7 @@ export function useImageList() {
       prev.map((img) => ({
         ...img,
         status: "pending" as const,
+        result_status: undefined,
         compressed_size: undefined,
         compressed_size_display: undefined,
         output_path: undefined,
@@ -83,6 +108,7 @@ export function useImageList() {
     removeImage,
     clearImages,
     updateImageStatus,
+    updateImageProgress,
     updateImageResult,
     resetStatus,
   };
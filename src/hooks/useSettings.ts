@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import type { CompressionSettings } from "../types";
+import type { CompressionSettings, JpegOptions } from "../types";
 import { getDefaultOutputDir } from "../lib/tauri";
 
 const SETTINGS_KEY = "picslim_settings";
@@ -42,5 +42,15 @@ export function useSettings() {
     setSettings((prev) => ({ ...prev, output_dir }));
   };
 
-  return { settings, updateQuality, updateOutputDir };
+  const updateJpeg = (jpeg: Partial<JpegOptions>) => {
+    setSettings((prev) => ({ ...prev, jpeg: { ...prev.jpeg, ...jpeg } }));
+  };
+
+  const updateWorkers = (
+    workers: Pick<CompressionSettings, "max_workers" | "background">,
+  ) => {
+    setSettings((prev) => ({ ...prev, ...workers }));
+  };
+
+  return { settings, updateQuality, updateOutputDir, updateJpeg, updateWorkers };
 }
@@ -4,6 +4,8 @@ import type {
   CompressionSettings,
   CompressionResult,
   CompressionEvent,
+  JobStatus,
+  ResumableJob,
 } from "../types";
 
 export async function loadImages(paths: string[]): Promise<ImageFileInfo[]> {
@@ -24,6 +26,26 @@ export async function compressImages(
   });
 }
 
+export async function pauseCompression(): Promise<boolean> {
+  return invoke<boolean>("pause_compression");
+}
+
+export async function resumeCompression(): Promise<boolean> {
+  return invoke<boolean>("resume_compression");
+}
+
+export async function getJobStatus(): Promise<JobStatus> {
+  return invoke<JobStatus>("get_job_status");
+}
+
+export async function getResumableJob(): Promise<ResumableJob | null> {
+  return invoke<ResumableJob | null>("get_resumable_job");
+}
+
+export async function discardResumableJob(): Promise<void> {
+  return invoke("discard_resumable_job");
+}
+
 export async function openOutputFolder(path: string): Promise<void> {
   return invoke("open_output_folder", { path });
 }
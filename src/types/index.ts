@@ -10,10 +10,20 @@ export interface ImageFileInfo {
   size_display: string;
   format: ImageFormat;
   thumbnail: string;
+  estimated_quality?: number | null; // JPEG source quality from its DQT tables
 }
 
+export type CompressionStatus =
+  | "compressed"
+  | "kept_original"
+  | "skipped"
+  | "failed";
+
 export interface ImageItem extends ImageFileInfo {
   status: ImageStatus;
+  result_status?: CompressionStatus;
+  stage?: CompressionStage; // current stage while compressing
+  stage_progress?: number; // 0.0–1.0 within the stage
   compressed_size?: number;
   compressed_size_display?: string;
   output_path?: string;
@@ -24,9 +34,62 @@ export interface ImageItem extends ImageFileInfo {
 export interface CompressionSettings {
   quality: number;
   output_dir: string;
+  max_workers?: number; // 0 = one per CPU core
+  background?: boolean;
+  ignore_cache?: boolean; // recompress inputs the result cache has already seen
+  skip_unoptimized?: boolean; // don't write outputs that aren't smaller
+  min_savings_percent?: number; // keep the original below this saving
+  min_savings_bytes?: number;
+  jpeg?: JpegOptions;
+  png?: PngOptions;
+  gif?: GifOptions;
+  metadata?: MetadataPolicy;
+}
+
+export type MetadataPolicy = "strip_all" | "keep_color_and_dpi" | "keep_all";
+
+export type JpegQualityPolicy = "reencode" | "cap_at_source" | "lossless_if_lower";
+
+export type ChromaSubsampling = "auto" | "yuv420" | "yuv422" | "yuv444";
+
+export type QuantTablePreset = "default" | "annex_k" | "flat" | "ms_ssim" | "psnr_hvs";
+
+export type CmykPolicy = "convert_to_srgb" | "keep";
+
+export interface JpegOptions {
+  quality_policy?: JpegQualityPolicy;
+  chroma_subsampling?: ChromaSubsampling;
+  progressive?: boolean;
+  trellis?: boolean;
+  quant_table?: QuantTablePreset;
+  cmyk_policy?: CmykPolicy;
+}
+
+export interface PngOptions {
+  lossless?: boolean;
+  min_quality?: number;
+  max_colors?: number;
+  dithering?: number;
+  posterize?: number;
+  effort?: number;
+  zopfli?: boolean;
+}
+
+export type GifLoopMode = "source" | "infinite" | { plays: number };
+
+export type GifTarget = "gif" | "webp" | "webp_lossless" | "apng";
+
+export interface GifOptions {
+  global_palette?: boolean;
+  max_fps?: number; // 0 = unlimited
+  loop_mode?: GifLoopMode;
+  speed?: number; // playback speed multiplier
+  min_delay?: number; // centiseconds, 0 = off
+  target?: GifTarget; // output takes the target's extension
 }
 
 export interface CompressionResult {
+  status: CompressionStatus;
   success: boolean;
   original_size: number;
   compressed_size: number;
@@ -34,10 +97,39 @@ export interface CompressionResult {
   error: string | null;
 }
 
-export interface CompressionEvent {
-  event_type: "start" | "complete" | "error";
-  image_id: string;
-  result: CompressionResult | null;
+export type CompressionStage = "decode" | "quantize" | "encode" | "optimize";
+
+export type CompressionEvent =
+  | { event_type: "start"; image_id: string }
+  | {
+      event_type: "stage";
+      image_id: string;
+      stage: CompressionStage;
+      progress: number; // 0.0–1.0 within the stage
+    }
+  | { event_type: "complete"; image_id: string; result: CompressionResult }
+  | { event_type: "error"; image_id: string; result: CompressionResult }
+  | {
+      event_type: "batch";
+      done: number;
+      total: number;
+      elapsed_ms: number;
+      eta_ms: number | null;
+    };
+
+export type JobState = "idle" | "running" | "paused";
+
+export interface JobStatus {
+  state: JobState;
+  total: number;
+  done: number;
+  in_flight: number;
+}
+
+export interface ResumableJob {
+  settings: CompressionSettings;
+  images: ImageFileInfo[]; // unfinished images, pass back to compress_images
+  finished: number;
 }
 
 export type AppState = "empty" | "ready" | "compressing" | "done";
@@ -45,4 +137,7 @@ export type AppState = "empty" | "ready" | "compressing" | "done";
 export interface ProgressBarProps {
   done: number;
   total: number;
+  etaMs?: number | null;
+  paused?: boolean;
+  inFlight?: number; // images still finishing after a pause
 }